@@ -0,0 +1,53 @@
+// breakin/crates/breakin-api/src/main.rs
+//! Read-only HTTP/JSON API over the database written by `breakin-indexer`:
+//!
+//! - `GET /matches?status=&limit=&offset=`: matches newest first, with live odds.
+//! - `GET /matches/{id}`: one match with its bets and bettor counts.
+//! - `GET /users/{pubkey}/bets`: bets a wallet currently owns, with derived payouts.
+//! - `GET /leaderboard?limit=`: wallets ranked by net winnings.
+mod routes;
+mod store;
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use breakin_indexer::Db;
+use clap::Parser;
+use log::info;
+use tower_http::cors::CorsLayer;
+
+use crate::store::Store;
+
+#[derive(Parser)]
+#[command(name = "breakin-api", version, about)]
+struct Args {
+    /// SQLite database written by breakin-indexer.
+    #[arg(long, env = "BREAKIN_DB", default_value = "breakin.db")]
+    db: PathBuf,
+    /// Address to listen on.
+    #[arg(long, env = "BREAKIN_API_LISTEN", default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let args = Args::parse();
+
+    let db =
+        Db::open_read_only(&args.db).with_context(|| format!("opening {}", args.db.display()))?;
+    // The data is public and read-only, so any origin, such as the web app, may read it.
+    let app = routes::router(Arc::new(Store::new(db))).layer(CorsLayer::permissive());
+
+    let listener = tokio::net::TcpListener::bind(args.listen).await?;
+    info!("Serving {} on http://{}", args.db.display(), args.listen);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+            info!("Shutting down");
+        })
+        .await?;
+    Ok(())
+}
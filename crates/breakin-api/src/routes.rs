@@ -0,0 +1,262 @@
+// breakin/crates/breakin-api/src/routes.rs
+//! HTTP routes. Everything is read-only JSON; amounts are lamports.
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use breakin_client::anchor_lang::prelude::Pubkey;
+use log::error;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::store::{Bet, LeaderboardEntry, Match, MatchDetail, Store};
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
+
+pub enum ApiError {
+    NotFound(String),
+    BadRequest(String),
+    Database(rusqlite::Error),
+}
+
+impl From<rusqlite::Error> for ApiError {
+    fn from(err: rusqlite::Error) -> Self {
+        ApiError::Database(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message),
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ApiError::Database(err) => {
+                error!("Query failed: {err}");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "database error".to_string(),
+                )
+            }
+        };
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<Json<T>, ApiError>;
+
+pub fn router(store: Arc<Store>) -> Router {
+    Router::new()
+        .route("/matches", get(list_matches))
+        .route("/matches/{id}", get(get_match))
+        .route("/users/{pubkey}/bets", get(user_bets))
+        .route("/leaderboard", get(leaderboard))
+        .with_state(store)
+}
+
+#[derive(Deserialize)]
+struct Page {
+    status: Option<String>,
+    limit: Option<u32>,
+    #[serde(default)]
+    offset: u32,
+}
+
+fn limit(requested: Option<u32>) -> u32 {
+    requested.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT)
+}
+
+async fn list_matches(
+    State(store): State<Arc<Store>>,
+    Query(page): Query<Page>,
+) -> ApiResult<Vec<Match>> {
+    Ok(Json(store.matches(
+        page.status.as_deref(),
+        limit(page.limit),
+        page.offset,
+    )?))
+}
+
+async fn get_match(
+    State(store): State<Arc<Store>>,
+    Path(match_id): Path<u64>,
+) -> ApiResult<MatchDetail> {
+    store
+        .match_detail(match_id)?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("match {match_id} not found")))
+}
+
+async fn user_bets(
+    State(store): State<Arc<Store>>,
+    Path(pubkey): Path<String>,
+) -> ApiResult<Vec<Bet>> {
+    let owner: Pubkey = pubkey
+        .parse()
+        .map_err(|_| ApiError::BadRequest(format!("invalid pubkey {pubkey}")))?;
+    Ok(Json(store.bets_by_owner(&owner.to_string())?))
+}
+
+#[derive(Deserialize)]
+struct LeaderboardQuery {
+    limit: Option<u32>,
+}
+
+async fn leaderboard(
+    State(store): State<Arc<Store>>,
+    Query(query): Query<LeaderboardQuery>,
+) -> ApiResult<Vec<LeaderboardEntry>> {
+    Ok(Json(store.leaderboard(limit(query.limit))?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use breakin_client::anchor_lang::{AccountSerialize, Space};
+    use breakin_client::program::{
+        BettingPool, ClaimAmounts, MatchStatus, Prediction, UserBet, Winner,
+    };
+    use breakin_indexer::Db;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    fn bytes<T: AccountSerialize + Space>(account: &T) -> Vec<u8> {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data.resize(8 + T::INIT_SPACE, 0);
+        data
+    }
+
+    /// Match 1 is settled with a claimed AI 1 bet; match 2 is open with bets both ways.
+    fn app(alice: Pubkey, bob: Pubkey) -> Router {
+        let db = Db::open_in_memory().unwrap();
+        let pools = [
+            BettingPool {
+                match_id: 1,
+                status: MatchStatus::Settled,
+                total_ai1_bets: 2_000,
+                total_ai2_bets: 2_000,
+                ..Default::default()
+            },
+            BettingPool {
+                match_id: 2,
+                total_ai1_bets: 3_000,
+                total_ai2_bets: 1_000,
+                ..Default::default()
+            },
+        ];
+        for pool in &pools {
+            db.upsert_match(&Pubkey::new_unique(), pool, &bytes(pool), 10)
+                .unwrap();
+        }
+        let bets = [
+            (1, alice, Prediction::Ai1, 2_000, true),
+            (1, bob, Prediction::Ai2, 2_000, false),
+            (2, alice, Prediction::Ai1, 3_000, false),
+            (2, bob, Prediction::Ai2, 1_000, false),
+        ];
+        for (match_id, better, prediction, amount, claimed) in bets {
+            let bet = UserBet {
+                better,
+                origin: better,
+                match_id,
+                prediction,
+                amount,
+                claimed,
+                ..Default::default()
+            };
+            let address = Pubkey::new_unique();
+            db.upsert_bet(&address, &bet, &bytes(&bet), 10).unwrap();
+            if claimed {
+                let claim = ClaimAmounts {
+                    payout: 4_000,
+                    stake: amount,
+                    outcome: Some(Winner::Ai1),
+                    ..Default::default()
+                };
+                db.insert_payout(
+                    &address.to_string(),
+                    match_id,
+                    &better.to_string(),
+                    &claim,
+                    11,
+                )
+                .unwrap();
+            }
+        }
+        for user in [alice, bob] {
+            db.refresh_user(&user.to_string(), 11).unwrap();
+        }
+        router(Arc::new(Store::new(db)))
+    }
+
+    async fn get_json(app: &Router, uri: &str) -> (StatusCode, Value) {
+        let response = app
+            .clone()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn lists_matches_with_live_odds() {
+        let app = app(Pubkey::new_unique(), Pubkey::new_unique());
+        let (status, body) = get_json(&app, "/matches").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["matchId"], 2);
+        assert_eq!(body[0]["odds"]["ai1Bps"], 13_333);
+        assert_eq!(body[0]["odds"]["ai2Bps"], 40_000);
+        assert_eq!(body[1]["matchId"], 1);
+
+        let (_, open) = get_json(&app, "/matches?status=OpenForBetting").await;
+        assert_eq!(open.as_array().unwrap().len(), 1);
+        let (_, page) = get_json(&app, "/matches?limit=1&offset=1").await;
+        assert_eq!(page[0]["matchId"], 1);
+    }
+
+    #[tokio::test]
+    async fn shows_one_match_with_its_bets() {
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let app = app(alice, bob);
+        let (status, body) = get_json(&app, "/matches/1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "Settled");
+        assert_eq!(body["ai1Bettors"], 1);
+        assert_eq!(body["ai2Bettors"], 1);
+        assert_eq!(body["bets"].as_array().unwrap().len(), 2);
+
+        let (status, body) = get_json(&app, "/matches/9").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "match 9 not found");
+    }
+
+    #[tokio::test]
+    async fn lists_user_bets_and_leaderboard() {
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let app = app(alice, bob);
+        let (status, body) = get_json(&app, &format!("/users/{alice}/bets")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["matchId"], 2);
+        assert_eq!(body[0]["payout"], Value::Null);
+        assert_eq!(body[1]["payout"], 4_000);
+        assert_eq!(body[1]["payoutEstimated"], true);
+
+        let (status, _) = get_json(&app, "/users/not-a-key/bets").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Bets on the open match 2 count for neither wallet yet.
+        let (_, board) = get_json(&app, "/leaderboard").await;
+        assert_eq!(board[0]["pubkey"], alice.to_string());
+        assert_eq!(board[0]["totalWagered"], 2_000);
+        assert_eq!(board[0]["net"], 2_000);
+        assert_eq!(board[1]["net"], -2_000);
+    }
+}
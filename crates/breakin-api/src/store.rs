@@ -0,0 +1,258 @@
+// breakin/crates/breakin-api/src/store.rs
+//! Read queries over the indexer's tables, returning the JSON shapes the API serves.
+use std::sync::Mutex;
+
+use breakin_client::anchor_lang::AccountDeserialize;
+use breakin_client::estimate::odds_bps;
+use breakin_client::program::{BettingPool, Prediction};
+use breakin_indexer::Db;
+use rusqlite::{OptionalExtension, Result, Row};
+use serde::Serialize;
+
+/// Columns selected for a [`Match`], in the order `Match::from_row` reads them.
+const MATCH_COLUMNS: &str = "match_id, address, status, market, total_ai1_bets, total_ai2_bets,
+    fee_bps, betting_deadline, ai1_move, ai2_move, winner, overturned, resolved_at,
+    updated_slot, pool_data";
+
+/// Columns selected for a [`Bet`], joined with its payout.
+const BET_COLUMNS: &str = "b.address, b.match_id, b.owner, b.origin, b.prediction, b.amount,
+    b.claimed, b.locked_odds_bps, p.payout, p.estimated";
+
+/// Current decimal odds per side in basis points, e.g. 25_000 pays 2.5x the stake.
+/// Fixed-odds markets report their quoted odds; parimutuel odds follow the pool totals
+/// and are `None` for a side nobody has backed.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Odds {
+    pub ai1_bps: Option<u64>,
+    pub ai2_bps: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Match {
+    pub match_id: u64,
+    pub address: Option<String>,
+    pub status: Option<String>,
+    pub market: Option<String>,
+    pub total_ai1_bets: u64,
+    pub total_ai2_bets: u64,
+    pub odds: Odds,
+    pub fee_bps: Option<u64>,
+    pub betting_deadline: Option<String>,
+    pub ai1_move: Option<String>,
+    pub ai2_move: Option<String>,
+    pub winner: Option<String>,
+    pub overturned: Option<bool>,
+    pub resolved_at: Option<i64>,
+    pub updated_slot: u64,
+}
+
+impl Match {
+    fn from_row(row: &Row) -> Result<Self> {
+        let pool_data: Option<Vec<u8>> = row.get(14)?;
+        let odds = pool_data
+            .and_then(|data| BettingPool::try_deserialize(&mut &data[..]).ok())
+            .map(|pool| Odds {
+                ai1_bps: odds_bps(&pool, Prediction::Ai1),
+                ai2_bps: odds_bps(&pool, Prediction::Ai2),
+            })
+            .unwrap_or_default();
+        Ok(Self {
+            match_id: row.get(0)?,
+            address: row.get(1)?,
+            status: row.get(2)?,
+            market: row.get(3)?,
+            total_ai1_bets: row.get(4)?,
+            total_ai2_bets: row.get(5)?,
+            odds,
+            fee_bps: row.get(6)?,
+            betting_deadline: row.get(7)?,
+            ai1_move: row.get(8)?,
+            ai2_move: row.get(9)?,
+            winner: row.get(10)?,
+            overturned: row.get(11)?,
+            resolved_at: row.get(12)?,
+            updated_slot: row.get(13)?,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bet {
+    pub address: String,
+    pub match_id: u64,
+    pub owner: String,
+    pub origin: String,
+    pub prediction: String,
+    pub amount: u64,
+    pub claimed: bool,
+    pub locked_odds_bps: u64,
+    /// What the claim paid, once the indexer has recorded it.
+    pub payout: Option<u64>,
+    /// Whether `payout` was estimated from account state because the claim's event was
+    /// missed; estimates can be short by a few lamports of rounding dust.
+    pub payout_estimated: Option<bool>,
+}
+
+impl Bet {
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(Self {
+            address: row.get(0)?,
+            match_id: row.get(1)?,
+            owner: row.get(2)?,
+            origin: row.get(3)?,
+            prediction: row.get(4)?,
+            amount: row.get(5)?,
+            claimed: row.get(6)?,
+            locked_odds_bps: row.get(7)?,
+            payout: row.get(8)?,
+            payout_estimated: row.get(9)?,
+        })
+    }
+}
+
+/// A match with every bet placed on it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchDetail {
+    #[serde(flatten)]
+    pub summary: Match,
+    pub ai1_bettors: u64,
+    pub ai2_bettors: u64,
+    pub bets: Vec<Bet>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardEntry {
+    pub pubkey: String,
+    pub bet_count: u64,
+    /// Staked on bets that have been paid or whose match is settled. Bets on open or
+    /// cancelled matches are left out until they pay.
+    pub total_wagered: u64,
+    pub total_paid_out: u64,
+    /// Paid out minus wagered. Unclaimed bets on settled matches count as losses.
+    pub net: i64,
+}
+
+/// The indexer database behind a lock, since SQLite connections cannot be shared
+/// between threads. Every query is short, so one connection is enough.
+pub struct Store {
+    db: Mutex<Db>,
+}
+
+impl Store {
+    pub fn new(db: Db) -> Self {
+        Self { db: Mutex::new(db) }
+    }
+
+    fn with_db<T>(&self, f: impl FnOnce(&Db) -> Result<T>) -> Result<T> {
+        // A panic while holding the lock cannot leave the read-only connection in a bad
+        // state, so a poisoned lock is still usable.
+        let db = self
+            .db
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&db)
+    }
+
+    /// Matches newest first, optionally only those with `status`.
+    pub fn matches(&self, status: Option<&str>, limit: u32, offset: u32) -> Result<Vec<Match>> {
+        self.with_db(|db| {
+            let mut statement = db.connection().prepare(&format!(
+                "SELECT {MATCH_COLUMNS} FROM matches
+                 WHERE ?1 IS NULL OR status = ?1
+                 ORDER BY match_id DESC LIMIT ?2 OFFSET ?3"
+            ))?;
+            let rows = statement.query_map((status, limit, offset), Match::from_row)?;
+            rows.collect()
+        })
+    }
+
+    pub fn match_detail(&self, match_id: u64) -> Result<Option<MatchDetail>> {
+        self.with_db(|db| {
+            let conn = db.connection();
+            let Some(summary) = conn
+                .query_row(
+                    &format!("SELECT {MATCH_COLUMNS} FROM matches WHERE match_id = ?1"),
+                    [match_id],
+                    Match::from_row,
+                )
+                .optional()?
+            else {
+                return Ok(None);
+            };
+            let mut statement = conn.prepare(&format!(
+                "SELECT {BET_COLUMNS} FROM bets b
+                 LEFT JOIN payouts p ON p.bet_address = b.address
+                 WHERE b.match_id = ?1 ORDER BY b.amount DESC, b.address"
+            ))?;
+            let bets = statement
+                .query_map([match_id], Bet::from_row)?
+                .collect::<Result<Vec<_>>>()?;
+            let bettors = |prediction: &str| {
+                let mut owners: Vec<&str> = bets
+                    .iter()
+                    .filter(|bet| bet.prediction == prediction)
+                    .map(|bet| bet.owner.as_str())
+                    .collect();
+                owners.sort_unstable();
+                owners.dedup();
+                owners.len() as u64
+            };
+            Ok(Some(MatchDetail {
+                ai1_bettors: bettors("Ai1"),
+                ai2_bettors: bettors("Ai2"),
+                summary,
+                bets,
+            }))
+        })
+    }
+
+    /// Bets currently owned by `owner`, newest match first.
+    pub fn bets_by_owner(&self, owner: &str) -> Result<Vec<Bet>> {
+        self.with_db(|db| {
+            let mut statement = db.connection().prepare(&format!(
+                "SELECT {BET_COLUMNS} FROM bets b
+                 LEFT JOIN payouts p ON p.bet_address = b.address
+                 WHERE b.owner = ?1 ORDER BY b.match_id DESC, b.address"
+            ))?;
+            let rows = statement.query_map([owner], Bet::from_row)?;
+            rows.collect()
+        })
+    }
+
+    /// Wallets ranked by net winnings.
+    pub fn leaderboard(&self, limit: u32) -> Result<Vec<LeaderboardEntry>> {
+        self.with_db(|db| {
+            let mut statement = db.connection().prepare(
+                "WITH decided AS (
+                    SELECT b.owner, b.amount FROM bets b
+                    LEFT JOIN payouts p ON p.bet_address = b.address
+                    LEFT JOIN matches m ON m.match_id = b.match_id
+                    WHERE p.bet_address IS NOT NULL OR m.status IN ('Settled', 'Closed')
+                 )
+                 SELECT pubkey, bet_count, wagered, total_paid_out, total_paid_out - wagered AS net
+                 FROM (
+                    SELECT u.pubkey, u.bet_count, u.total_paid_out,
+                        (SELECT COALESCE(SUM(amount), 0) FROM decided WHERE owner = u.pubkey)
+                            AS wagered
+                    FROM users u WHERE u.bet_count > 0
+                 )
+                 ORDER BY net DESC, wagered DESC, pubkey LIMIT ?1",
+            )?;
+            let rows = statement.query_map([limit], |row| {
+                Ok(LeaderboardEntry {
+                    pubkey: row.get(0)?,
+                    bet_count: row.get(1)?,
+                    total_wagered: row.get(2)?,
+                    total_paid_out: row.get(3)?,
+                    net: row.get(4)?,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+}
@@ -0,0 +1,132 @@
+// breakin/crates/breakin-cli/src/config.rs
+//! RPC URL and keypair selection. Flags win over the Solana CLI config file, which wins
+//! over the local validator defaults, so `solana config set` is all a runbook needs.
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use solana_keypair::{read_keypair_file, Keypair};
+
+pub const DEFAULT_RPC_URL: &str = "http://127.0.0.1:8899";
+pub const DEFAULT_COMMITMENT: &str = "confirmed";
+
+/// The subset of `~/.config/solana/cli/config.yml` used here.
+#[derive(Debug, Default, Deserialize)]
+struct SolanaCliConfig {
+    json_rpc_url: Option<String>,
+    keypair_path: Option<String>,
+    commitment: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Config {
+    pub rpc_url: String,
+    pub keypair_path: PathBuf,
+    pub commitment: String,
+}
+
+impl Config {
+    /// Resolves the configuration from flags and the config file at `path`, or the
+    /// default Solana CLI config location when `path` is `None`. A missing default file
+    /// is fine; a missing explicit one is not.
+    pub fn load(
+        path: Option<&Path>,
+        rpc_url: Option<String>,
+        keypair_path: Option<PathBuf>,
+    ) -> Result<Self> {
+        let file = match path {
+            Some(path) => read(path)?,
+            None => match default_config_path() {
+                Some(path) if path.exists() => read(&path)?,
+                _ => SolanaCliConfig::default(),
+            },
+        };
+        Ok(Self::resolve(file, rpc_url, keypair_path))
+    }
+
+    fn resolve(
+        file: SolanaCliConfig,
+        rpc_url: Option<String>,
+        keypair_path: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            rpc_url: rpc_url
+                .or(file.json_rpc_url)
+                .unwrap_or_else(|| DEFAULT_RPC_URL.to_string()),
+            keypair_path: keypair_path
+                .or(file.keypair_path.map(PathBuf::from))
+                .unwrap_or_else(default_keypair_path),
+            commitment: file
+                .commitment
+                .unwrap_or_else(|| DEFAULT_COMMITMENT.to_string()),
+        }
+    }
+
+    pub fn keypair(&self) -> Result<Keypair> {
+        read_keypair_file(&self.keypair_path)
+            .map_err(|err| anyhow::anyhow!("{err}"))
+            .with_context(|| format!("reading keypair {}", self.keypair_path.display()))
+    }
+}
+
+fn read(path: &Path) -> Result<SolanaCliConfig> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("reading config {}", path.display()))?;
+    serde_yaml::from_str(&contents).with_context(|| format!("parsing config {}", path.display()))
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config/solana/cli/config.yml"))
+}
+
+fn default_keypair_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_default()
+        .join(".config/solana/id.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOLANA_CONFIG: &str = "---
+json_rpc_url: \"https://api.devnet.solana.com\"
+websocket_url: \"\"
+keypair_path: /home/ops/.config/solana/ops.json
+address_labels:
+  \"11111111111111111111111111111111\": System Program
+commitment: finalized
+";
+
+    #[test]
+    fn reads_the_solana_cli_config() {
+        let file: SolanaCliConfig = serde_yaml::from_str(SOLANA_CONFIG).unwrap();
+        let config = Config::resolve(file, None, None);
+        assert_eq!(config.rpc_url, "https://api.devnet.solana.com");
+        assert_eq!(
+            config.keypair_path,
+            PathBuf::from("/home/ops/.config/solana/ops.json")
+        );
+        assert_eq!(config.commitment, "finalized");
+    }
+
+    #[test]
+    fn flags_override_the_file() {
+        let file: SolanaCliConfig = serde_yaml::from_str(SOLANA_CONFIG).unwrap();
+        let config = Config::resolve(
+            file,
+            Some(DEFAULT_RPC_URL.to_string()),
+            Some(PathBuf::from("ops.json")),
+        );
+        assert_eq!(config.rpc_url, DEFAULT_RPC_URL);
+        assert_eq!(config.keypair_path, PathBuf::from("ops.json"));
+    }
+
+    #[test]
+    fn defaults_to_the_local_validator() {
+        let config = Config::resolve(SolanaCliConfig::default(), None, None);
+        assert_eq!(config.rpc_url, DEFAULT_RPC_URL);
+        assert_eq!(config.commitment, DEFAULT_COMMITMENT);
+        assert!(config.keypair_path.ends_with(".config/solana/id.json"));
+    }
+}
@@ -0,0 +1,512 @@
+// breakin/crates/breakin-cli/src/main.rs
+//! Operator tool for the arena program. Every command that changes state is signed and
+//! paid for by the configured keypair; see `config` for how it and the RPC URL are chosen.
+//! Read-only commands never load the keypair, so they work on machines without one.
+mod config;
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use anyhow::{bail, Context, Result};
+use breakin_client::anchor_lang::prelude::Pubkey;
+use breakin_client::anchor_lang::solana_program::instruction::Instruction;
+use breakin_client::program::{
+    BetLimits, BettingPool, BettingTime, GameConfig, ImbalanceGuard, MarketKind, MatchStatus, Move,
+    Prediction,
+};
+use breakin_client::{estimate, fetch, instructions, pda, RpcClient};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use crate::config::Config;
+
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+#[derive(Parser)]
+#[command(name = "breakin-cli", version, about)]
+struct Cli {
+    /// Solana CLI config file. Defaults to ~/.config/solana/cli/config.yml.
+    #[arg(long, short = 'C', global = true, env = "BREAKIN_CONFIG")]
+    config: Option<PathBuf>,
+    /// RPC URL, overriding the config file.
+    #[arg(long, short = 'u', global = true, env = "BREAKIN_RPC_URL")]
+    url: Option<String>,
+    /// Keypair that signs and pays, overriding the config file.
+    #[arg(long, short = 'k', global = true, env = "BREAKIN_KEYPAIR")]
+    keypair: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the game state and treasury, making the signer the game authority.
+    Init(GameConfigArgs),
+    /// Create the next match with the signer as its pool authority and resolver.
+    CreateMatch(CreateMatchArgs),
+    /// Open betting on a scheduled match once its opening time has passed.
+    OpenBetting { match_id: u64 },
+    /// Close betting on a match whose deadline has passed.
+    CheckDeadline { match_id: u64 },
+    /// Record the AI moves for a match awaiting resolution.
+    Resolve {
+        match_id: u64,
+        ai1_move: MoveArg,
+        ai2_move: MoveArg,
+    },
+    /// Settle a resolved match once its dispute window has passed.
+    Finalize { match_id: u64 },
+    /// Cancel a match whose resolver missed the resolution deadline.
+    Cancel { match_id: u64 },
+    /// Print a match, its pool and its result.
+    ShowMatch { match_id: u64 },
+    /// List bets on a match or owned by a wallet. Defaults to the signer's bets.
+    ListBets {
+        #[arg(long)]
+        match_id: Option<u64>,
+        #[arg(long)]
+        owner: Option<Pubkey>,
+    },
+    /// Claim winnings or a refund for one of the signer's bets.
+    Claim {
+        match_id: u64,
+        /// Wallet that originally placed the bet, if it was bought from someone else.
+        #[arg(long)]
+        origin: Option<Pubkey>,
+    },
+    /// Treasury balance and withdrawals.
+    #[command(subcommand)]
+    Treasury(TreasuryCommand),
+}
+
+#[derive(Subcommand)]
+enum TreasuryCommand {
+    /// Print the treasury balance and lifetime collections.
+    Show,
+    /// Withdraw lamports from the treasury. Only the game authority can do this.
+    Withdraw {
+        lamports: u64,
+        /// Defaults to the signer.
+        #[arg(long)]
+        recipient: Option<Pubkey>,
+    },
+}
+
+/// Game configuration for `init`. The defaults assume ~400ms slots.
+#[derive(Args)]
+struct GameConfigArgs {
+    #[arg(long, default_value_t = 1_500)]
+    default_betting_duration_slots: u64,
+    #[arg(long, default_value_t = 150)]
+    min_betting_duration_slots: u64,
+    /// 0 for no limit.
+    #[arg(long, default_value_t = 216_000)]
+    max_betting_duration_slots: u64,
+    #[arg(long, default_value_t = 0)]
+    min_bet_threshold_floor: u64,
+    /// 0 for no limit.
+    #[arg(long, default_value_t = 0)]
+    min_bet_threshold_ceiling: u64,
+    #[arg(long, default_value_t = 0)]
+    platform_fee_bps: u64,
+    #[arg(long, default_value_t = 1_500)]
+    resolution_timeout_slots: u64,
+    #[arg(long, default_value_t = 150)]
+    dispute_window_slots: u64,
+    #[arg(long, default_value_t = LAMPORTS_PER_SOL / 10)]
+    dispute_bond_lamports: u64,
+    #[arg(long, default_value_t = LAMPORTS_PER_SOL / 10)]
+    resolver_bond_lamports: u64,
+    #[arg(long, default_value_t = 1_512_000)]
+    claim_window_slots: u64,
+}
+
+impl From<&GameConfigArgs> for GameConfig {
+    fn from(args: &GameConfigArgs) -> Self {
+        GameConfig {
+            default_betting_duration_slots: args.default_betting_duration_slots,
+            min_betting_duration_slots: args.min_betting_duration_slots,
+            max_betting_duration_slots: args.max_betting_duration_slots,
+            min_bet_threshold_floor: args.min_bet_threshold_floor,
+            min_bet_threshold_ceiling: args.min_bet_threshold_ceiling,
+            platform_fee_bps: args.platform_fee_bps,
+            resolution_timeout_slots: args.resolution_timeout_slots,
+            dispute_window_slots: args.dispute_window_slots,
+            dispute_bond_lamports: args.dispute_bond_lamports,
+            resolver_bond_lamports: args.resolver_bond_lamports,
+            claim_window_slots: args.claim_window_slots,
+        }
+    }
+}
+
+#[derive(Args)]
+struct CreateMatchArgs {
+    /// Total lamports that must be bet for the match to go ahead.
+    #[arg(long, default_value_t = 0)]
+    min_bet_threshold: u64,
+    /// Close betting at this slot. Defaults to the game's default betting duration.
+    #[arg(long, conflicts_with = "deadline_unix")]
+    deadline_slot: Option<u64>,
+    /// Close betting at this Unix timestamp.
+    #[arg(long)]
+    deadline_unix: Option<i64>,
+    /// Only open betting at this slot; the match stays scheduled until then.
+    #[arg(long, conflicts_with = "opens_at_unix")]
+    opens_at_slot: Option<u64>,
+    /// Only open betting at this Unix timestamp.
+    #[arg(long)]
+    opens_at_unix: Option<i64>,
+    #[arg(long)]
+    min_bet: Option<u64>,
+    #[arg(long)]
+    max_bet: Option<u64>,
+    #[arg(long)]
+    max_per_user: Option<u64>,
+    #[arg(long)]
+    max_pool_size: Option<u64>,
+    /// Cancel the match if one side outweighs the other by more than this ratio.
+    #[arg(long)]
+    max_side_ratio_bps: Option<u64>,
+    /// Offer fixed decimal odds in basis points instead of a parimutuel pool.
+    #[arg(long, num_args = 2, value_names = ["AI1_BPS", "AI2_BPS"])]
+    fixed_odds: Option<Vec<u64>>,
+}
+
+impl From<&CreateMatchArgs> for instructions::CreateMatchArgs {
+    fn from(args: &CreateMatchArgs) -> Self {
+        let time = |slot: Option<u64>, unix: Option<i64>| {
+            slot.map(BettingTime::Slot)
+                .or(unix.map(BettingTime::UnixTimestamp))
+        };
+        instructions::CreateMatchArgs {
+            min_bet_threshold_lamports: args.min_bet_threshold,
+            betting_deadline: time(args.deadline_slot, args.deadline_unix),
+            betting_opens_at: time(args.opens_at_slot, args.opens_at_unix),
+            bet_limits: BetLimits {
+                min_bet: args.min_bet,
+                max_bet: args.max_bet,
+                max_per_user: args.max_per_user,
+                max_pool_size: args.max_pool_size,
+            },
+            imbalance_guard: args
+                .max_side_ratio_bps
+                .map(|max_side_ratio_bps| ImbalanceGuard { max_side_ratio_bps }),
+            market: match args.fixed_odds.as_deref() {
+                Some(&[ai1_odds_bps, ai2_odds_bps]) => MarketKind::FixedOdds {
+                    ai1_odds_bps,
+                    ai2_odds_bps,
+                },
+                _ => MarketKind::Parimutuel,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum MoveArg {
+    Rock,
+    Paper,
+    Scissors,
+}
+
+impl From<MoveArg> for Move {
+    fn from(arg: MoveArg) -> Self {
+        match arg {
+            MoveArg::Rock => Move::Rock,
+            MoveArg::Paper => Move::Paper,
+            MoveArg::Scissors => Move::Scissors,
+        }
+    }
+}
+
+struct App {
+    rpc: RpcClient,
+    config: Config,
+    signer: OnceLock<Keypair>,
+}
+
+impl App {
+    fn new(config: Config) -> Self {
+        Self {
+            rpc: RpcClient::new_with_commitment(&config.rpc_url, &config.commitment),
+            config,
+            signer: OnceLock::new(),
+        }
+    }
+
+    /// The configured keypair, read on first use.
+    fn signer(&self) -> Result<&Keypair> {
+        if let Some(signer) = self.signer.get() {
+            return Ok(signer);
+        }
+        let signer = self.config.keypair()?;
+        Ok(self.signer.get_or_init(|| signer))
+    }
+
+    fn me(&self) -> Result<Pubkey> {
+        Ok(self.signer()?.pubkey())
+    }
+
+    async fn send(&self, instruction: Instruction) -> Result<()> {
+        let signature = self
+            .rpc
+            .send_and_confirm(&[instruction], self.signer()?, &[])
+            .await?;
+        println!("Signature: {signature}");
+        Ok(())
+    }
+
+    async fn betting_pool(&self, match_id: u64) -> Result<BettingPool> {
+        fetch::betting_pool(&self.rpc, match_id)
+            .await?
+            .with_context(|| format!("match #{match_id} does not exist or has been swept"))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let config = Config::load(cli.config.as_deref(), cli.url, cli.keypair)?;
+    let ctx = App::new(config);
+
+    match cli.command {
+        Command::Init(args) => {
+            ctx.send(instructions::initialize_game(&ctx.me()?, (&args).into()))
+                .await?;
+            println!("Game state: {}", pda::game_state());
+            println!("Treasury:   {}", pda::treasury());
+        }
+        Command::CreateMatch(args) => {
+            let match_id = fetch::game_state(&ctx.rpc).await?.next_match_id;
+            ctx.send(instructions::create_match(
+                &ctx.me()?,
+                match_id,
+                (&args).into(),
+            ))
+            .await?;
+            println!("Created match #{match_id}: {}", pda::betting_pool(match_id));
+        }
+        Command::OpenBetting { match_id } => {
+            ctx.send(instructions::open_betting(match_id)).await?;
+        }
+        Command::CheckDeadline { match_id } => {
+            ctx.send(instructions::check_betting_deadline(match_id))
+                .await?;
+            let pool = ctx.betting_pool(match_id).await?;
+            println!("Match #{match_id} is now {:?}", pool.status);
+        }
+        Command::Resolve {
+            match_id,
+            ai1_move,
+            ai2_move,
+        } => {
+            ctx.send(instructions::resolve_match(
+                &ctx.me()?,
+                match_id,
+                ai1_move.into(),
+                ai2_move.into(),
+            ))
+            .await?;
+        }
+        Command::Finalize { match_id } => {
+            ctx.send(instructions::finalize_match(match_id)).await?;
+        }
+        Command::Cancel { match_id } => {
+            ctx.send(instructions::cancel_unresolved_match(match_id))
+                .await?;
+        }
+        Command::ShowMatch { match_id } => show_match(&ctx, match_id).await?,
+        Command::ListBets { match_id, owner } => {
+            let bets = match (match_id, owner) {
+                (Some(match_id), owner) => fetch::bets_for_match(&ctx.rpc, match_id)
+                    .await?
+                    .into_iter()
+                    .filter(|(_, bet)| owner.is_none_or(|owner| bet.better == owner))
+                    .collect(),
+                (None, Some(owner)) => fetch::bets_by_owner(&ctx.rpc, &owner).await?,
+                (None, None) => fetch::bets_by_owner(&ctx.rpc, &ctx.me()?).await?,
+            };
+            if bets.is_empty() {
+                println!("No bets found.");
+            }
+            for (address, bet) in bets {
+                println!(
+                    "{address}  match #{:<6} {:<4} {:>16} SOL  owner {}{}",
+                    bet.match_id,
+                    side(bet.prediction),
+                    sol(bet.amount),
+                    bet.better,
+                    if bet.claimed { "  (claimed)" } else { "" }
+                );
+            }
+        }
+        Command::Claim { match_id, origin } => {
+            let me = ctx.me()?;
+            let pool = ctx.betting_pool(match_id).await?;
+            let origin = origin.unwrap_or(me);
+            let Some(bet) = fetch::user_bet(&ctx.rpc, &origin, match_id).await? else {
+                bail!("no bet placed by {origin} on match #{match_id}");
+            };
+            if bet.claimed {
+                bail!("this bet has already been claimed");
+            }
+            let record = fetch::match_record(&ctx.rpc, match_id).await?;
+            let claim = estimate::claimable(&pool, record.as_ref(), &bet)?;
+            println!(
+                "Claiming {} SOL ({} SOL fee)",
+                sol(claim.payout),
+                sol(claim.fee)
+            );
+            ctx.send(instructions::claim_winnings(&me, &pool, &bet))
+                .await?;
+        }
+        Command::Treasury(TreasuryCommand::Show) => {
+            let treasury = fetch::treasury(&ctx.rpc).await?;
+            let balance = ctx.rpc.get_balance(&pda::treasury()).await?;
+            println!("Treasury:        {}", pda::treasury());
+            println!("Balance:         {} SOL", sol(balance));
+            println!("Total collected: {} SOL", sol(treasury.total_collected));
+        }
+        Command::Treasury(TreasuryCommand::Withdraw {
+            lamports,
+            recipient,
+        }) => {
+            let me = ctx.me()?;
+            let recipient = recipient.unwrap_or(me);
+            ctx.send(instructions::withdraw_treasury(&me, &recipient, lamports))
+                .await?;
+            println!("Withdrew {} SOL to {recipient}", sol(lamports));
+        }
+    }
+    Ok(())
+}
+
+async fn show_match(ctx: &App, match_id: u64) -> Result<()> {
+    let pool = ctx.betting_pool(match_id).await?;
+    let slot = ctx.rpc.get_slot().await?;
+    println!("Match #{match_id} ({})", pda::betting_pool(match_id));
+    println!("  Status:           {:?}", pool.status);
+    println!("  Authority:        {}", pool.authority);
+    println!("  Market:           {:?}", pool.market);
+    println!("  Betting opens:    {:?}", pool.betting_opens_at);
+    println!(
+        "  Betting deadline: {:?} (current slot {slot})",
+        pool.betting_deadline
+    );
+    println!("  Minimum pool:     {} SOL", sol(pool.min_bet_threshold));
+    for prediction in [Prediction::Ai1, Prediction::Ai2] {
+        let total = match prediction {
+            Prediction::Ai1 => pool.total_ai1_bets,
+            Prediction::Ai2 => pool.total_ai2_bets,
+        };
+        let odds = estimate::odds_bps(&pool, prediction)
+            .map(|odds| format!("{:.4}x", odds as f64 / 10_000.0))
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "  {:<4} {:>16} SOL  odds {odds}",
+            side(prediction),
+            sol(total)
+        );
+    }
+    if pool.fee_bps > 0 {
+        println!("  Platform fee:     {} bps", pool.fee_bps);
+    }
+    if pool.status == MatchStatus::AwaitingResolution {
+        println!("  Resolve by slot:  {}", pool.resolution_deadline_slot);
+    }
+    if let Some(record) = fetch::match_record(&ctx.rpc, match_id).await? {
+        println!(
+            "  Result:           {:?} vs {:?}, winner {:?}{}",
+            record.ai1_move,
+            record.ai2_move,
+            record.winner,
+            if record.overturned {
+                " (overturned)"
+            } else {
+                ""
+            }
+        );
+    }
+    if pool.claim_deadline_slot > 0 {
+        println!(
+            "  Claims:           {} paid, {} SOL out, open until slot {}",
+            pool.claimed_count,
+            sol(pool.total_paid_out),
+            pool.claim_deadline_slot
+        );
+    }
+    Ok(())
+}
+
+fn side(prediction: Prediction) -> &'static str {
+    match prediction {
+        Prediction::Ai1 => "AI 1",
+        Prediction::Ai2 => "AI 2",
+    }
+}
+
+fn sol(lamports: u64) -> String {
+    format!(
+        "{}.{:09}",
+        lamports / LAMPORTS_PER_SOL,
+        lamports % LAMPORTS_PER_SOL
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn create_match_flags_map_to_instruction_args() {
+        let cli = Cli::parse_from([
+            "breakin-cli",
+            "create-match",
+            "--deadline-slot",
+            "500",
+            "--max-bet",
+            "10",
+            "--fixed-odds",
+            "20000",
+            "18000",
+        ]);
+        let Command::CreateMatch(args) = cli.command else {
+            panic!("expected create-match");
+        };
+        let args = instructions::CreateMatchArgs::from(&args);
+        assert_eq!(args.betting_deadline, Some(BettingTime::Slot(500)));
+        assert_eq!(args.betting_opens_at, None);
+        assert_eq!(args.bet_limits.max_bet, Some(10));
+        assert_eq!(
+            args.market,
+            MarketKind::FixedOdds {
+                ai1_odds_bps: 20_000,
+                ai2_odds_bps: 18_000,
+            }
+        );
+    }
+
+    #[test]
+    fn keypair_is_only_read_when_signing() {
+        let ctx = App::new(Config {
+            rpc_url: config::DEFAULT_RPC_URL.to_string(),
+            keypair_path: PathBuf::from("/nonexistent/id.json"),
+            commitment: config::DEFAULT_COMMITMENT.to_string(),
+        });
+        let err = ctx.me().unwrap_err();
+        assert!(format!("{err:#}").contains("/nonexistent/id.json"));
+    }
+
+    #[test]
+    fn formats_lamports_as_sol() {
+        assert_eq!(sol(1_500_000_000), "1.500000000");
+        assert_eq!(sol(1), "0.000000001");
+    }
+}
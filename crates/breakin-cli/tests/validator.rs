@@ -0,0 +1,106 @@
+// breakin/crates/breakin-cli/tests/validator.rs
+//! End-to-end run of a match through the CLI against a local validator. Ignored by
+//! default; start a fresh validator with the program deployed, then run
+//!
+//! ```text
+//! solana-test-validator --reset --bpf-program <program id> target/deploy/breakin.so
+//! cargo test -p breakin-cli --test validator -- --ignored
+//! ```
+//!
+//! The signer comes from `BREAKIN_KEYPAIR` or the Solana CLI default and must be funded,
+//! which the validator's faucet does for the default keypair.
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+use breakin_client::program::{MatchStatus, Prediction};
+use breakin_client::{fetch, instructions, RpcClient};
+use solana_keypair::{read_keypair_file, Keypair};
+use solana_signer::Signer;
+
+const RPC_URL: &str = "http://127.0.0.1:8899";
+
+fn keypair_path() -> PathBuf {
+    std::env::var_os("BREAKIN_KEYPAIR")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config/solana/id.json")))
+        .expect("no keypair configured")
+}
+
+/// Runs the CLI against the local validator and returns its stdout.
+fn cli(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_breakin-cli"))
+        .args(["--url", RPC_URL, "--keypair"])
+        .arg(keypair_path())
+        .args(args)
+        .output()
+        .expect("running breakin-cli");
+    assert!(
+        output.status.success(),
+        "breakin-cli {args:?} failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+async fn wait_for_slot(rpc: &RpcClient, slot: u64) {
+    while rpc.get_slot().await.unwrap() < slot {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs a local solana-test-validator with the program deployed"]
+async fn create_bet_resolve_and_settle_a_match() {
+    let rpc = RpcClient::new_with_commitment(RPC_URL, "confirmed");
+    let signer: Keypair = read_keypair_file(keypair_path()).expect("reading keypair");
+
+    if fetch::game_state(&rpc).await.is_err() {
+        cli(&[
+            "init",
+            "--default-betting-duration-slots",
+            "20",
+            "--min-betting-duration-slots",
+            "10",
+            "--dispute-window-slots",
+            "5",
+        ]);
+    }
+    let match_id = fetch::game_state(&rpc).await.unwrap().next_match_id;
+    let deadline = rpc.get_slot().await.unwrap() + 20;
+    cli(&["create-match", "--deadline-slot", &deadline.to_string()]);
+    let pool = fetch::betting_pool(&rpc, match_id).await.unwrap().unwrap();
+    assert_eq!(pool.status, MatchStatus::OpenForBetting);
+
+    rpc.send_and_confirm(
+        &[instructions::place_bet(
+            &signer.pubkey(),
+            &pool,
+            1_000_000,
+            Prediction::Ai1,
+        )],
+        &signer,
+        &[],
+    )
+    .await
+    .unwrap();
+
+    wait_for_slot(&rpc, deadline).await;
+    let id = match_id.to_string();
+    let status = cli(&["check-deadline", &id]);
+    assert!(status.contains("AwaitingResolution"), "{status}");
+    cli(&["resolve", &id, "rock", "scissors"]);
+
+    let pool = fetch::betting_pool(&rpc, match_id).await.unwrap().unwrap();
+    wait_for_slot(&rpc, pool.challenge_deadline_slot).await;
+    cli(&["finalize", &id]);
+    let pool = fetch::betting_pool(&rpc, match_id).await.unwrap().unwrap();
+    assert_eq!(pool.status, MatchStatus::Settled);
+
+    cli(&["claim", &id]);
+    let bet = fetch::user_bet(&rpc, &signer.pubkey(), match_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(bet.claimed);
+}
@@ -0,0 +1,28 @@
+// breakin/crates/breakin-client/src/error.rs
+use anchor_lang::prelude::Pubkey;
+use solana_signature::Signature;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("RPC error {code}: {message}")]
+    Rpc { code: i64, message: String },
+    #[error("unexpected RPC response: {0}")]
+    InvalidResponse(String),
+    #[error("account {0} does not exist")]
+    AccountNotFound(Pubkey),
+    #[error("failed to decode account {0}: {1}")]
+    Decode(Pubkey, anchor_lang::error::Error),
+    #[error("failed to sign transaction: {0}")]
+    Signing(#[from] solana_signer::SignerError),
+    #[error("transaction {0} failed: {1}")]
+    TransactionFailed(Signature, String),
+    #[error("transaction {0} was not confirmed in time")]
+    ConfirmationTimeout(Signature),
+    #[error("payout estimate failed: {0}")]
+    Estimate(anchor_lang::error::Error),
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
@@ -0,0 +1,160 @@
+// breakin/crates/breakin-client/src/estimate.rs
+//! Payout estimates built on the program's own settlement rules, so what a client
+//! shows matches what a claim pays.
+use breakin::payout::{fixed_odds_payout, pro_rata};
+use breakin::settlement::{predicted, settle, Bet, Outcome, PoolTotals, Settlement};
+use breakin::{
+    compute_claim, BettingPool, ClaimAmounts, MatchRecord, Prediction, UserBet, Winner,
+    BPS_DENOMINATOR,
+};
+
+use crate::error::{ClientError, Result};
+
+fn totals(pool: &BettingPool) -> PoolTotals {
+    PoolTotals {
+        ai1: pool.total_ai1_bets,
+        ai2: pool.total_ai2_bets,
+    }
+}
+
+/// Decimal odds in basis points currently offered on `side`, before fees. Parimutuel
+/// odds move with every bet and are `None` while nobody has backed `side`.
+pub fn odds_bps(pool: &BettingPool, side: Prediction) -> Option<u64> {
+    if let Some(odds) = pool.market.odds_for(side) {
+        return Some(odds);
+    }
+    let totals = totals(pool);
+    let (on_side, total) = match side {
+        Prediction::Ai1 => (totals.ai1, totals.ai1.checked_add(totals.ai2)?),
+        Prediction::Ai2 => (totals.ai2, totals.ai1.checked_add(totals.ai2)?),
+    };
+    pro_rata(total, BPS_DENOMINATOR, on_side).ok()
+}
+
+/// What a new bet of `amount` on `prediction` would pay if `winner` won, assuming no
+/// further bets are placed.
+pub fn estimate_payout(
+    pool: &BettingPool,
+    prediction: Prediction,
+    amount: u64,
+    winner: Winner,
+) -> Result<Settlement> {
+    if let Some(odds) = pool.market.odds_for(prediction) {
+        if predicted(prediction, winner) {
+            return Ok(Settlement {
+                payout: fixed_odds_payout(amount, odds).map_err(ClientError::Estimate)?,
+                fee: 0,
+                stake: amount,
+            });
+        }
+    }
+    let mut totals = totals(pool);
+    let side = match prediction {
+        Prediction::Ai1 => &mut totals.ai1,
+        Prediction::Ai2 => &mut totals.ai2,
+    };
+    *side = side
+        .checked_add(amount)
+        .ok_or(ClientError::Estimate(breakin::RpsError::Overflow.into()))?;
+    settle(
+        totals,
+        Outcome::Settled(winner),
+        Bet { prediction, amount },
+        pool.fee_bps,
+    )
+    .map_err(ClientError::Estimate)
+}
+
+/// What claiming `bet` would pay right now. `record` is required once the match is
+/// settled.
+pub fn claimable(
+    pool: &BettingPool,
+    record: Option<&MatchRecord>,
+    bet: &UserBet,
+) -> Result<ClaimAmounts> {
+    compute_claim(
+        pool,
+        record,
+        bet.prediction,
+        bet.amount,
+        bet.locked_odds_bps,
+    )
+    .map_err(ClientError::Estimate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use breakin::{MarketKind, MatchStatus};
+
+    const SOL: u64 = 1_000_000_000;
+
+    fn pool(ai1: u64, ai2: u64) -> BettingPool {
+        BettingPool {
+            total_ai1_bets: ai1,
+            total_ai2_bets: ai2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parimutuel_odds_follow_the_pool() {
+        let lopsided = pool(SOL, 3 * SOL);
+        assert_eq!(odds_bps(&lopsided, Prediction::Ai1), Some(40_000));
+        assert_eq!(odds_bps(&lopsided, Prediction::Ai2), Some(13_333));
+        assert_eq!(odds_bps(&pool(0, SOL), Prediction::Ai1), None);
+    }
+
+    #[test]
+    fn fixed_odds_ignore_the_pool() {
+        let pool = BettingPool {
+            market: MarketKind::FixedOdds {
+                ai1_odds_bps: 25_000,
+                ai2_odds_bps: 15_000,
+            },
+            ..pool(SOL, 0)
+        };
+        assert_eq!(odds_bps(&pool, Prediction::Ai2), Some(15_000));
+        let estimate = estimate_payout(&pool, Prediction::Ai1, 2 * SOL, Winner::Ai1).unwrap();
+        assert_eq!(estimate.payout, 5 * SOL);
+        let estimate = estimate_payout(&pool, Prediction::Ai1, 2 * SOL, Winner::Ai2).unwrap();
+        assert_eq!(estimate.payout, 0);
+    }
+
+    #[test]
+    fn estimate_counts_the_new_stake() {
+        // 1 SOL more on Ai1 makes it 2 vs 2: a winning bet doubles, less the fee on profit.
+        let pool = BettingPool {
+            fee_bps: 500,
+            ..pool(SOL, 2 * SOL)
+        };
+        let estimate = estimate_payout(&pool, Prediction::Ai1, SOL, Winner::Ai1).unwrap();
+        assert_eq!(estimate.fee, SOL / 20);
+        assert_eq!(estimate.payout, 2 * SOL - SOL / 20);
+        let draw = estimate_payout(&pool, Prediction::Ai1, SOL, Winner::Draw).unwrap();
+        assert_eq!(draw.payout, SOL);
+    }
+
+    #[test]
+    fn claimable_matches_the_program() {
+        let pool = BettingPool {
+            match_id: 3,
+            status: MatchStatus::Settled,
+            ..pool(SOL, SOL)
+        };
+        let record = MatchRecord {
+            match_id: 3,
+            winner: Winner::Ai2,
+            ..Default::default()
+        };
+        let bet = UserBet {
+            match_id: 3,
+            prediction: Prediction::Ai2,
+            amount: SOL,
+            ..Default::default()
+        };
+        let claim = claimable(&pool, Some(&record), &bet).unwrap();
+        assert_eq!(claim.payout, 2 * SOL);
+        assert!(claimable(&pool, None, &bet).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+// breakin/crates/breakin-client/src/fetch.rs
+//! Fetching and decoding program accounts.
+//!
+//! Listing helpers filter on the current account size, so version 0 accounts that still
+//! need a `migrate_*` call are left out rather than failing to decode.
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator, Space};
+use breakin::{BettingPool, GameState, HouseVault, MatchRecord, Treasury, UserBet, ID};
+
+use crate::error::{ClientError, Result};
+use crate::pda;
+use crate::rpc::{Filter, RpcClient};
+
+/// Offset of `UserBet::better`: discriminator, then `version`.
+pub const USER_BET_BETTER_OFFSET: usize = 8 + 1;
+/// Offset of `UserBet::match_id`, right after `better`.
+pub const USER_BET_MATCH_ID_OFFSET: usize = USER_BET_BETTER_OFFSET + 32;
+
+fn decode<T: AccountDeserialize>(address: &Pubkey, data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..]).map_err(|err| ClientError::Decode(*address, err))
+}
+
+/// The account at `address`, or `None` if it does not exist.
+pub async fn account<T: AccountDeserialize>(
+    rpc: &RpcClient,
+    address: &Pubkey,
+) -> Result<Option<T>> {
+    rpc.get_account_data(address)
+        .await?
+        .map(|data| decode(address, &data))
+        .transpose()
+}
+
+/// Like [`account`], but a missing account is an error.
+pub async fn required<T: AccountDeserialize>(rpc: &RpcClient, address: &Pubkey) -> Result<T> {
+    account(rpc, address)
+        .await?
+        .ok_or(ClientError::AccountNotFound(*address))
+}
+
+/// Every current-layout account of type `T` matching `filters`.
+pub async fn all<T: AccountDeserialize + Discriminator + Space>(
+    rpc: &RpcClient,
+    filters: &[Filter],
+) -> Result<Vec<(Pubkey, T)>> {
+    let mut all_filters = vec![
+        Filter::DataSize((8 + T::INIT_SPACE) as u64),
+        Filter::Memcmp {
+            offset: 0,
+            bytes: T::DISCRIMINATOR.to_vec(),
+        },
+    ];
+    all_filters.extend_from_slice(filters);
+    rpc.get_program_accounts(&ID, &all_filters)
+        .await?
+        .into_iter()
+        .map(|(address, data)| Ok((address, decode(&address, &data)?)))
+        .collect()
+}
+
+pub async fn game_state(rpc: &RpcClient) -> Result<GameState> {
+    required(rpc, &pda::game_state()).await
+}
+
+pub async fn treasury(rpc: &RpcClient) -> Result<Treasury> {
+    required(rpc, &pda::treasury()).await
+}
+
+pub async fn house_vault(rpc: &RpcClient) -> Result<Option<HouseVault>> {
+    account(rpc, &pda::house_vault()).await
+}
+
+pub async fn betting_pool(rpc: &RpcClient, match_id: u64) -> Result<Option<BettingPool>> {
+    account(rpc, &pda::betting_pool(match_id)).await
+}
+
+pub async fn match_record(rpc: &RpcClient, match_id: u64) -> Result<Option<MatchRecord>> {
+    account(rpc, &pda::match_record(match_id)).await
+}
+
+/// The bet originally placed by `origin` on `match_id`.
+pub async fn user_bet(rpc: &RpcClient, origin: &Pubkey, match_id: u64) -> Result<Option<UserBet>> {
+    account(rpc, &pda::user_bet(origin, match_id)).await
+}
+
+/// All betting pools that have not been swept yet.
+pub async fn betting_pools(rpc: &RpcClient) -> Result<Vec<(Pubkey, BettingPool)>> {
+    all(rpc, &[]).await
+}
+
+pub async fn bets_for_match(rpc: &RpcClient, match_id: u64) -> Result<Vec<(Pubkey, UserBet)>> {
+    all(
+        rpc,
+        &[Filter::Memcmp {
+            offset: USER_BET_MATCH_ID_OFFSET,
+            bytes: match_id.to_le_bytes().to_vec(),
+        }],
+    )
+    .await
+}
+
+/// Bets currently owned by `better`, including ones bought from other wallets.
+pub async fn bets_by_owner(rpc: &RpcClient, better: &Pubkey) -> Result<Vec<(Pubkey, UserBet)>> {
+    all(
+        rpc,
+        &[Filter::Memcmp {
+            offset: USER_BET_BETTER_OFFSET,
+            bytes: better.to_bytes().to_vec(),
+        }],
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AccountSerialize;
+
+    #[test]
+    fn user_bet_filter_offsets_match_the_layout() {
+        let bet = UserBet {
+            better: Pubkey::new_unique(),
+            match_id: 0x0102_0304_0506_0708,
+            ..Default::default()
+        };
+        let mut data = Vec::new();
+        bet.try_serialize(&mut data).unwrap();
+        assert_eq!(
+            &data[USER_BET_BETTER_OFFSET..USER_BET_BETTER_OFFSET + 32],
+            bet.better.as_ref()
+        );
+        assert_eq!(
+            &data[USER_BET_MATCH_ID_OFFSET..USER_BET_MATCH_ID_OFFSET + 8],
+            bet.match_id.to_le_bytes()
+        );
+        assert_eq!(data.len(), 8 + UserBet::INIT_SPACE);
+    }
+}
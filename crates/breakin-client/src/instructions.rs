@@ -0,0 +1,739 @@
+// breakin/crates/breakin-client/src/instructions.rs
+//! One builder per program instruction.
+//!
+//! Builders for instructions whose optional accounts depend on the state of the match
+//! take the fetched `BettingPool` and work out which accounts to pass from it, so callers
+//! never have to know when a house vault or match record is required.
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, spl_associated_token_account};
+use anchor_spl::token;
+use breakin::{
+    accounts, instruction, BetLimits, BettingPool, BettingTime, CorrectedMoves, GameConfig,
+    ImbalanceGuard, MarketKind, MatchStatus, Move, Prediction, UserBet, ID,
+};
+
+use crate::pda;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Only settled pools need their match record to pay out.
+fn match_record_if_settled(pool: &BettingPool) -> Option<Pubkey> {
+    (pool.status == MatchStatus::Settled).then(|| pda::match_record(pool.match_id))
+}
+
+/// Fixed-odds markets are staked into and paid from the house vault.
+fn house_vault_if_fixed_odds(pool: &BettingPool) -> Option<Pubkey> {
+    pool.market.is_fixed_odds().then(pda::house_vault)
+}
+
+pub fn initialize_game(authority: &Pubkey, config: GameConfig) -> Instruction {
+    build(
+        accounts::InitializeGame {
+            game_state: pda::game_state(),
+            treasury: pda::treasury(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeGame { config },
+    )
+}
+
+pub fn update_config(authority: &Pubkey, config: GameConfig) -> Instruction {
+    build(
+        accounts::UpdateConfig {
+            game_state: pda::game_state(),
+            authority: *authority,
+        },
+        instruction::UpdateConfig { config },
+    )
+}
+
+/// Arguments for `create_match`. `None` timings fall back to the game configuration.
+#[derive(Clone, Debug, Default)]
+pub struct CreateMatchArgs {
+    pub min_bet_threshold_lamports: u64,
+    pub betting_deadline: Option<BettingTime>,
+    pub betting_opens_at: Option<BettingTime>,
+    pub bet_limits: BetLimits,
+    pub imbalance_guard: Option<ImbalanceGuard>,
+    pub market: MarketKind,
+}
+
+/// `match_id` must be the game state's current `next_match_id`.
+pub fn create_match(creator: &Pubkey, match_id: u64, args: CreateMatchArgs) -> Instruction {
+    build(
+        accounts::CreateMatch {
+            game_state: pda::game_state(),
+            betting_pool: pda::betting_pool(match_id),
+            betting_pool_authority: pda::betting_pool_authority(match_id),
+            resolver_bond: pda::resolver_bond(match_id),
+            match_creator_signer: *creator,
+            system_program: system_program::ID,
+        },
+        instruction::CreateMatch {
+            min_bet_threshold_lamports: args.min_bet_threshold_lamports,
+            betting_deadline: args.betting_deadline,
+            betting_opens_at: args.betting_opens_at,
+            bet_limits: args.bet_limits,
+            imbalance_guard: args.imbalance_guard,
+            market: args.market,
+        },
+    )
+}
+
+pub fn open_betting(match_id: u64) -> Instruction {
+    build(
+        accounts::OpenBetting {
+            betting_pool: pda::betting_pool(match_id),
+        },
+        instruction::OpenBetting {},
+    )
+}
+
+fn place_bet_accounts(better: &Pubkey, pool: &BettingPool) -> accounts::PlaceBet {
+    accounts::PlaceBet {
+        betting_pool: pda::betting_pool(pool.match_id),
+        user_bet: pda::user_bet(better, pool.match_id),
+        house_vault: house_vault_if_fixed_odds(pool),
+        position_mint: None,
+        position_token_account: None,
+        betting_pool_authority: None,
+        token_program: None,
+        better: *better,
+        system_program: system_program::ID,
+    }
+}
+
+pub fn place_bet(
+    better: &Pubkey,
+    pool: &BettingPool,
+    amount: u64,
+    prediction: Prediction,
+) -> Instruction {
+    build(
+        place_bet_accounts(better, pool),
+        instruction::PlaceBet { amount, prediction },
+    )
+}
+
+pub fn place_bet_legacy(
+    better: &Pubkey,
+    pool: &BettingPool,
+    amount: u64,
+    prediction_raw: u8,
+) -> Instruction {
+    build(
+        place_bet_accounts(better, pool),
+        instruction::PlaceBetLegacy {
+            amount,
+            prediction_raw,
+        },
+    )
+}
+
+/// Places a bet and mints position tokens for it. The better's token account must
+/// exist; see [`create_position_token_account`].
+pub fn place_bet_tokenized(
+    better: &Pubkey,
+    pool: &BettingPool,
+    amount: u64,
+    prediction: Prediction,
+) -> Instruction {
+    let match_id = pool.match_id;
+    build(
+        accounts::PlaceBet {
+            position_mint: Some(pda::position_mint(match_id, prediction)),
+            position_token_account: Some(pda::position_token_account(better, match_id, prediction)),
+            betting_pool_authority: Some(pda::betting_pool_authority(match_id)),
+            token_program: Some(token::ID),
+            ..place_bet_accounts(better, pool)
+        },
+        instruction::PlaceBet { amount, prediction },
+    )
+}
+
+/// Creates `owner`'s position token account for one side if it does not exist yet.
+pub fn create_position_token_account(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    match_id: u64,
+    side: Prediction,
+) -> Instruction {
+    spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+        payer,
+        owner,
+        &pda::position_mint(match_id, side),
+        &token::ID,
+    )
+}
+
+pub fn check_betting_deadline(match_id: u64) -> Instruction {
+    build(
+        accounts::CheckBettingDeadline {
+            betting_pool: pda::betting_pool(match_id),
+        },
+        instruction::CheckBettingDeadline {},
+    )
+}
+
+fn resolve_match_accounts(resolver: &Pubkey, match_id: u64) -> accounts::ResolveMatch {
+    accounts::ResolveMatch {
+        game_state: pda::game_state(),
+        betting_pool: pda::betting_pool(match_id),
+        match_record: pda::match_record(match_id),
+        resolver_signer: *resolver,
+        system_program: system_program::ID,
+    }
+}
+
+pub fn resolve_match(
+    resolver: &Pubkey,
+    match_id: u64,
+    ai1_move: Move,
+    ai2_move: Move,
+) -> Instruction {
+    build(
+        resolve_match_accounts(resolver, match_id),
+        instruction::ResolveMatch { ai1_move, ai2_move },
+    )
+}
+
+pub fn resolve_match_legacy(
+    resolver: &Pubkey,
+    match_id: u64,
+    ai1_move_raw: u8,
+    ai2_move_raw: u8,
+) -> Instruction {
+    build(
+        resolve_match_accounts(resolver, match_id),
+        instruction::ResolveMatchLegacy {
+            ai1_move_raw,
+            ai2_move_raw,
+        },
+    )
+}
+
+pub fn cancel_unresolved_match(match_id: u64) -> Instruction {
+    build(
+        accounts::CancelUnresolvedMatch {
+            betting_pool: pda::betting_pool(match_id),
+            resolver_bond: pda::resolver_bond(match_id),
+            treasury: pda::treasury(),
+        },
+        instruction::CancelUnresolvedMatch {},
+    )
+}
+
+/// `resolver` is the pool authority that posted the bond and receives it back.
+pub fn release_resolver_bond(match_id: u64, resolver: &Pubkey) -> Instruction {
+    build(
+        accounts::ReleaseResolverBond {
+            betting_pool: pda::betting_pool(match_id),
+            resolver_bond: pda::resolver_bond(match_id),
+            resolver: *resolver,
+        },
+        instruction::ReleaseResolverBond {},
+    )
+}
+
+pub fn finalize_match(match_id: u64) -> Instruction {
+    build(
+        accounts::FinalizeMatch {
+            betting_pool: pda::betting_pool(match_id),
+        },
+        instruction::FinalizeMatch {},
+    )
+}
+
+pub fn raise_dispute(disputer: &Pubkey, match_id: u64) -> Instruction {
+    build(
+        accounts::RaiseDispute {
+            game_state: pda::game_state(),
+            betting_pool: pda::betting_pool(match_id),
+            dispute: pda::dispute(match_id),
+            disputer: *disputer,
+            system_program: system_program::ID,
+        },
+        instruction::RaiseDispute {},
+    )
+}
+
+pub fn adjudicate_dispute(
+    authority: &Pubkey,
+    match_id: u64,
+    disputer: &Pubkey,
+    corrected_moves: Option<CorrectedMoves>,
+) -> Instruction {
+    build(
+        accounts::AdjudicateDispute {
+            game_state: pda::game_state(),
+            betting_pool: pda::betting_pool(match_id),
+            match_record: pda::match_record(match_id),
+            dispute: pda::dispute(match_id),
+            disputer: *disputer,
+            resolver_bond: pda::resolver_bond(match_id),
+            treasury: pda::treasury(),
+            authority: *authority,
+        },
+        instruction::AdjudicateDispute { corrected_moves },
+    )
+}
+
+pub fn withdraw_treasury(authority: &Pubkey, recipient: &Pubkey, amount: u64) -> Instruction {
+    build(
+        accounts::WithdrawTreasury {
+            game_state: pda::game_state(),
+            treasury: pda::treasury(),
+            recipient: *recipient,
+            authority: *authority,
+        },
+        instruction::WithdrawTreasury { amount },
+    )
+}
+
+pub fn initialize_house_vault(authority: &Pubkey, withdrawal_cooldown_slots: u64) -> Instruction {
+    build(
+        accounts::InitializeHouseVault {
+            game_state: pda::game_state(),
+            house_vault: pda::house_vault(),
+            lp_mint: pda::house_lp_mint(),
+            lp_escrow: pda::house_lp_escrow(),
+            authority: *authority,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeHouseVault {
+            withdrawal_cooldown_slots,
+        },
+    )
+}
+
+pub fn deposit_liquidity(depositor: &Pubkey, amount: u64) -> Instruction {
+    build(
+        accounts::DepositLiquidity {
+            house_vault: pda::house_vault(),
+            lp_mint: pda::house_lp_mint(),
+            depositor_lp_account: pda::lp_token_account(depositor),
+            lp_escrow: pda::house_lp_escrow(),
+            depositor: *depositor,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::DepositLiquidity { amount },
+    )
+}
+
+pub fn request_withdrawal(owner: &Pubkey, shares: u64) -> Instruction {
+    build(
+        accounts::RequestWithdrawal {
+            house_vault: pda::house_vault(),
+            owner_lp_account: pda::lp_token_account(owner),
+            lp_escrow: pda::house_lp_escrow(),
+            withdrawal: pda::lp_withdrawal(owner),
+            owner: *owner,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::RequestWithdrawal { shares },
+    )
+}
+
+pub fn cancel_withdrawal(owner: &Pubkey) -> Instruction {
+    build(
+        accounts::CancelWithdrawal {
+            house_vault: pda::house_vault(),
+            owner_lp_account: pda::lp_token_account(owner),
+            lp_escrow: pda::house_lp_escrow(),
+            withdrawal: pda::lp_withdrawal(owner),
+            owner: *owner,
+            token_program: token::ID,
+        },
+        instruction::CancelWithdrawal {},
+    )
+}
+
+pub fn complete_withdrawal(owner: &Pubkey) -> Instruction {
+    build(
+        accounts::CompleteWithdrawal {
+            house_vault: pda::house_vault(),
+            lp_mint: pda::house_lp_mint(),
+            lp_escrow: pda::house_lp_escrow(),
+            withdrawal: pda::lp_withdrawal(owner),
+            owner: *owner,
+            token_program: token::ID,
+        },
+        instruction::CompleteWithdrawal {},
+    )
+}
+
+pub fn collect_pool_fees(match_id: u64) -> Instruction {
+    build(
+        accounts::CollectPoolFees {
+            betting_pool: pda::betting_pool(match_id),
+            house_vault: pda::house_vault(),
+        },
+        instruction::CollectPoolFees {},
+    )
+}
+
+pub fn release_house_liability(pool: &BettingPool) -> Instruction {
+    build(
+        accounts::ReleaseHouseLiability {
+            betting_pool: pda::betting_pool(pool.match_id),
+            match_record: match_record_if_settled(pool),
+            house_vault: pda::house_vault(),
+        },
+        instruction::ReleaseHouseLiability {},
+    )
+}
+
+/// `authority` must be the pool authority.
+pub fn enable_position_tokens(authority: &Pubkey, match_id: u64) -> Instruction {
+    build(
+        accounts::EnablePositionTokens {
+            betting_pool: pda::betting_pool(match_id),
+            betting_pool_authority: pda::betting_pool_authority(match_id),
+            ai1_position_mint: pda::position_mint(match_id, Prediction::Ai1),
+            ai2_position_mint: pda::position_mint(match_id, Prediction::Ai2),
+            authority: *authority,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::EnablePositionTokens {},
+    )
+}
+
+pub fn redeem_position(
+    holder: &Pubkey,
+    pool: &BettingPool,
+    side: Prediction,
+    amount: u64,
+) -> Instruction {
+    let match_id = pool.match_id;
+    build(
+        accounts::RedeemPosition {
+            betting_pool: pda::betting_pool(match_id),
+            match_record: match_record_if_settled(pool),
+            position_mint: pda::position_mint(match_id, side),
+            holder_token_account: pda::position_token_account(holder, match_id, side),
+            holder: *holder,
+            token_program: token::ID,
+        },
+        instruction::RedeemPosition { side, amount },
+    )
+}
+
+pub fn list_bet(seller: &Pubkey, bet: &UserBet, price_lamports: u64) -> Instruction {
+    let user_bet = pda::user_bet(&bet.origin, bet.match_id);
+    build(
+        accounts::ListBet {
+            betting_pool: pda::betting_pool(bet.match_id),
+            user_bet,
+            listing: pda::bet_listing(&user_bet),
+            seller: *seller,
+            system_program: system_program::ID,
+        },
+        instruction::ListBet { price_lamports },
+    )
+}
+
+/// `user_bet` is the address of the listed bet.
+pub fn cancel_listing(seller: &Pubkey, user_bet: &Pubkey) -> Instruction {
+    build(
+        accounts::CancelListing {
+            listing: pda::bet_listing(user_bet),
+            seller: *seller,
+        },
+        instruction::CancelListing {},
+    )
+}
+
+/// Buys a listed bet from its current owner, `bet.better`.
+pub fn buy_bet(buyer: &Pubkey, bet: &UserBet) -> Instruction {
+    let user_bet = pda::user_bet(&bet.origin, bet.match_id);
+    build(
+        accounts::BuyBet {
+            betting_pool: pda::betting_pool(bet.match_id),
+            user_bet,
+            listing: pda::bet_listing(&user_bet),
+            seller: bet.better,
+            buyer: *buyer,
+            system_program: system_program::ID,
+        },
+        instruction::BuyBet {},
+    )
+}
+
+pub fn claim_winnings(better: &Pubkey, pool: &BettingPool, bet: &UserBet) -> Instruction {
+    build(
+        accounts::ClaimWinnings {
+            betting_pool: pda::betting_pool(pool.match_id),
+            betting_pool_authority: pda::betting_pool_authority(pool.match_id),
+            match_record: match_record_if_settled(pool),
+            house_vault: house_vault_if_fixed_odds(pool),
+            user_bet: pda::user_bet(&bet.origin, bet.match_id),
+            better: *better,
+            system_program: system_program::ID,
+        },
+        instruction::ClaimWinnings {},
+    )
+}
+
+/// Claims every `(pool, bet)` pair in one transaction. Keep the batch small enough to
+/// fit the transaction size limit; three accounts are added per bet.
+pub fn claim_many(better: &Pubkey, claims: &[(&BettingPool, &UserBet)]) -> Instruction {
+    let house_vault = claims
+        .iter()
+        .any(|(pool, _)| pool.market.is_fixed_odds())
+        .then(pda::house_vault);
+    let mut ix = build(
+        accounts::ClaimMany {
+            house_vault,
+            better: *better,
+        },
+        instruction::ClaimMany {},
+    );
+    for (pool, bet) in claims {
+        ix.accounts.extend([
+            AccountMeta::new(pda::betting_pool(pool.match_id), false),
+            AccountMeta::new_readonly(match_record_if_settled(pool).unwrap_or(ID), false),
+            AccountMeta::new(pda::user_bet(&bet.origin, bet.match_id), false),
+        ]);
+    }
+    ix
+}
+
+/// Pays out `bets` of one pool to their current owners, rewarding `keeper`.
+pub fn distribute(keeper: &Pubkey, pool: &BettingPool, bets: &[UserBet]) -> Instruction {
+    let mut ix = build(
+        accounts::Distribute {
+            betting_pool: pda::betting_pool(pool.match_id),
+            match_record: match_record_if_settled(pool),
+            house_vault: house_vault_if_fixed_odds(pool),
+            keeper: *keeper,
+        },
+        instruction::Distribute {},
+    );
+    for bet in bets {
+        ix.accounts.extend([
+            AccountMeta::new(pda::user_bet(&bet.origin, bet.match_id), false),
+            AccountMeta::new(bet.better, false),
+        ]);
+    }
+    ix
+}
+
+pub fn sweep_unclaimed(pool: &BettingPool) -> Instruction {
+    let needs_vault = pool.market.is_fixed_odds() || pool.fees_accrued > pool.fees_collected;
+    build(
+        accounts::SweepUnclaimed {
+            betting_pool: pda::betting_pool(pool.match_id),
+            house_vault: needs_vault.then(pda::house_vault),
+            treasury: pda::treasury(),
+        },
+        instruction::SweepUnclaimed {},
+    )
+}
+
+pub fn migrate_game_state(authority: &Pubkey, config: GameConfig) -> Instruction {
+    build(
+        accounts::MigrateGameState {
+            game_state: pda::game_state(),
+            treasury: pda::treasury(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateGameState { config },
+    )
+}
+
+/// `claimed_by` lists the original betters of every bet already claimed from the pool.
+/// Settled pools with claims also pass their match record, which must be migrated first.
+pub fn migrate_betting_pool(
+    payer: &Pubkey,
+    match_id: u64,
+    settled: bool,
+    claimed_by: &[Pubkey],
+) -> Instruction {
+    let mut ix = build(
+        accounts::MigrateBettingPool {
+            betting_pool: pda::betting_pool(match_id),
+            game_state: pda::game_state(),
+            match_record: (settled && !claimed_by.is_empty()).then(|| pda::match_record(match_id)),
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateBettingPool {},
+    );
+    let mut claimed: Vec<Pubkey> = claimed_by
+        .iter()
+        .map(|better| pda::user_bet(better, match_id))
+        .collect();
+    claimed.sort();
+    claimed.dedup();
+    ix.accounts.extend(
+        claimed
+            .into_iter()
+            .map(|user_bet| AccountMeta::new_readonly(user_bet, false)),
+    );
+    ix
+}
+
+pub fn migrate_match_record(payer: &Pubkey, match_id: u64) -> Instruction {
+    build(
+        accounts::MigrateAccount {
+            account: pda::match_record(match_id),
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateMatchRecord {},
+    )
+}
+
+/// Version 0 bets live at the address derived from their original better.
+pub fn migrate_user_bet(payer: &Pubkey, better: &Pubkey, match_id: u64) -> Instruction {
+    build(
+        accounts::MigrateAccount {
+            account: pda::user_bet(better, match_id),
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateUserBet {},
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(market: MarketKind, status: MatchStatus) -> BettingPool {
+        BettingPool {
+            match_id: 7,
+            market,
+            status,
+            ..Default::default()
+        }
+    }
+
+    const FIXED_ODDS: MarketKind = MarketKind::FixedOdds {
+        ai1_odds_bps: 20_000,
+        ai2_odds_bps: 20_000,
+    };
+
+    #[test]
+    fn absent_optional_accounts_are_the_program_id() {
+        let better = Pubkey::new_unique();
+        let ix = place_bet(
+            &better,
+            &pool(MarketKind::Parimutuel, MatchStatus::OpenForBetting),
+            1,
+            Prediction::Ai1,
+        );
+        // house_vault, position_mint, position_token_account, betting_pool_authority, token_program
+        assert!(ix.accounts[2..7].iter().all(|meta| meta.pubkey == ID));
+
+        let ix = place_bet(
+            &better,
+            &pool(FIXED_ODDS, MatchStatus::OpenForBetting),
+            1,
+            Prediction::Ai1,
+        );
+        assert_eq!(ix.accounts[2].pubkey, pda::house_vault());
+    }
+
+    #[test]
+    fn legacy_and_typed_place_bet_share_accounts() {
+        let better = Pubkey::new_unique();
+        let pool = pool(MarketKind::Parimutuel, MatchStatus::OpenForBetting);
+        let typed = place_bet(&better, &pool, 5, Prediction::Ai2);
+        let legacy = place_bet_legacy(&better, &pool, 5, Prediction::Ai2 as u8);
+        assert_eq!(typed.accounts, legacy.accounts);
+        assert_ne!(typed.data[..8], legacy.data[..8]);
+        // Same Borsh encoding after the discriminator.
+        assert_eq!(typed.data[8..], legacy.data[8..]);
+    }
+
+    #[test]
+    fn match_record_only_passed_for_settled_pools() {
+        let better = Pubkey::new_unique();
+        let bet = UserBet {
+            better,
+            origin: better,
+            match_id: 7,
+            ..Default::default()
+        };
+        let settled = pool(MarketKind::Parimutuel, MatchStatus::Settled);
+        let cancelled = pool(MarketKind::Parimutuel, MatchStatus::CancelledDueToLowBets);
+
+        let ix = claim_winnings(&better, &settled, &bet);
+        assert_eq!(ix.accounts[2].pubkey, pda::match_record(7));
+        let ix = claim_winnings(&better, &cancelled, &bet);
+        assert_eq!(ix.accounts[2].pubkey, ID);
+
+        let ix = claim_many(&better, &[(&settled, &bet), (&cancelled, &bet)]);
+        let triples: Vec<_> = ix.accounts[2..].chunks(3).collect();
+        assert_eq!(triples.len(), 2);
+        assert_eq!(triples[0][1].pubkey, pda::match_record(7));
+        assert_eq!(triples[1][1].pubkey, ID);
+        assert!(triples.iter().all(|t| t[0].is_writable && t[2].is_writable));
+    }
+
+    #[test]
+    fn market_builders_address_resold_bets_by_origin() {
+        let seller = Pubkey::new_unique();
+        let buyer = Pubkey::new_unique();
+        let bet = UserBet {
+            better: buyer,
+            origin: seller,
+            match_id: 7,
+            ..Default::default()
+        };
+        let user_bet = pda::user_bet(&seller, 7);
+        let listing = pda::bet_listing(&user_bet);
+
+        // The buyer relists the bet it bought; the UserBet stays at the seller's PDA.
+        let ix = list_bet(&buyer, &bet, 1_000);
+        assert_eq!(ix.accounts[1].pubkey, user_bet);
+        assert_eq!(ix.accounts[2].pubkey, listing);
+        assert!(ix.accounts[3].is_signer);
+
+        // Cancelling closes the listing back to whoever listed it.
+        let ix = cancel_listing(&buyer, &user_bet);
+        assert_eq!(ix.accounts[0].pubkey, listing);
+        assert!(ix.accounts[0].is_writable);
+        assert_eq!(ix.accounts[1].pubkey, buyer);
+        assert!(ix.accounts[1].is_signer && ix.accounts[1].is_writable);
+
+        // Buying pays the current owner, not the origin.
+        let next_buyer = Pubkey::new_unique();
+        let ix = buy_bet(&next_buyer, &bet);
+        assert_eq!(ix.accounts[2].pubkey, listing);
+        assert_eq!(ix.accounts[3].pubkey, buyer);
+        assert!(!ix.accounts[3].is_signer);
+        assert_eq!(ix.accounts[4].pubkey, next_buyer);
+    }
+
+    #[test]
+    fn migrated_pools_list_claimed_bets_in_address_order() {
+        let betters = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let ix = migrate_betting_pool(&betters[0], 7, true, &[betters[1], betters[0], betters[1]]);
+        assert_eq!(ix.accounts[2].pubkey, pda::match_record(7));
+        let claimed: Vec<_> = ix.accounts[5..].iter().map(|meta| meta.pubkey).collect();
+        let mut expected: Vec<_> = betters.iter().map(|b| pda::user_bet(b, 7)).collect();
+        expected.sort();
+        assert_eq!(claimed, expected);
+
+        let ix = migrate_betting_pool(&betters[0], 7, true, &[]);
+        assert_eq!(ix.accounts[2].pubkey, ID);
+        assert_eq!(ix.accounts.len(), 5);
+    }
+}
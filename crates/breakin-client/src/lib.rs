@@ -0,0 +1,18 @@
+// breakin/crates/breakin-client/src/lib.rs
+//! Rust client SDK for the arena program: PDA helpers, instruction builders, an async
+//! RPC client with account fetch helpers, and payout estimation.
+//!
+//! Account types, enums and the pure payout rules come straight from the program crate,
+//! built with `no-entrypoint`, and are re-exported as [`program`].
+pub mod error;
+pub mod estimate;
+pub mod fetch;
+pub mod instructions;
+pub mod pda;
+pub mod rpc;
+
+pub use anchor_lang;
+pub use breakin as program;
+pub use breakin::ID as PROGRAM_ID;
+pub use error::{ClientError, Result};
+pub use rpc::RpcClient;
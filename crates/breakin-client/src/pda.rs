@@ -0,0 +1,77 @@
+// breakin/crates/breakin-client/src/pda.rs
+//! Program derived addresses, using the same seeds as the program's account constraints.
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address;
+use breakin::{Prediction, ID};
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ID).0
+}
+
+pub fn game_state() -> Pubkey {
+    find(&[b"game_state"])
+}
+
+pub fn treasury() -> Pubkey {
+    find(&[b"treasury"])
+}
+
+pub fn betting_pool(match_id: u64) -> Pubkey {
+    find(&[b"betting_pool", &match_id.to_le_bytes()])
+}
+
+pub fn betting_pool_authority(match_id: u64) -> Pubkey {
+    find(&[b"betting_pool_authority", &match_id.to_le_bytes()])
+}
+
+pub fn resolver_bond(match_id: u64) -> Pubkey {
+    find(&[b"resolver_bond", &match_id.to_le_bytes()])
+}
+
+pub fn match_record(match_id: u64) -> Pubkey {
+    find(&[b"match_record", &match_id.to_le_bytes()])
+}
+
+pub fn dispute(match_id: u64) -> Pubkey {
+    find(&[b"dispute", &match_id.to_le_bytes()])
+}
+
+/// A bet stays at the address derived from the wallet that placed it (`origin`), even
+/// after it has been sold to someone else.
+pub fn user_bet(origin: &Pubkey, match_id: u64) -> Pubkey {
+    find(&[b"user_bet", origin.as_ref(), &match_id.to_le_bytes()])
+}
+
+pub fn position_mint(match_id: u64, side: Prediction) -> Pubkey {
+    find(&[b"position_mint", &match_id.to_le_bytes(), &[side as u8]])
+}
+
+pub fn bet_listing(user_bet: &Pubkey) -> Pubkey {
+    find(&[b"bet_listing", user_bet.as_ref()])
+}
+
+pub fn house_vault() -> Pubkey {
+    find(&[b"house_vault"])
+}
+
+pub fn house_lp_mint() -> Pubkey {
+    find(&[b"house_lp_mint"])
+}
+
+pub fn house_lp_escrow() -> Pubkey {
+    find(&[b"house_lp_escrow"])
+}
+
+pub fn lp_withdrawal(owner: &Pubkey) -> Pubkey {
+    find(&[b"lp_withdrawal", owner.as_ref()])
+}
+
+/// `owner`'s associated LP token account.
+pub fn lp_token_account(owner: &Pubkey) -> Pubkey {
+    get_associated_token_address(owner, &house_lp_mint())
+}
+
+/// `owner`'s associated token account for one side's position tokens.
+pub fn position_token_account(owner: &Pubkey, match_id: u64, side: Prediction) -> Pubkey {
+    get_associated_token_address(owner, &position_mint(match_id, side))
+}
@@ -0,0 +1,323 @@
+// breakin/crates/breakin-client/src/rpc.rs
+//! A small async JSON-RPC client covering the calls the arena tooling needs.
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_keypair::Keypair;
+use solana_signature::Signature;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::error::{ClientError, Result};
+
+/// How long `send_and_confirm` waits before giving up on a transaction.
+pub const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// `getProgramAccounts` filters.
+#[derive(Clone, Debug)]
+pub enum Filter {
+    DataSize(u64),
+    Memcmp { offset: usize, bytes: Vec<u8> },
+}
+
+impl Filter {
+    fn to_json(&self) -> Value {
+        match self {
+            Filter::DataSize(size) => json!({ "dataSize": size }),
+            Filter::Memcmp { offset, bytes } => json!({
+                "memcmp": {
+                    "offset": offset,
+                    "bytes": BASE64_STANDARD.encode(bytes),
+                    "encoding": "base64",
+                }
+            }),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(default)]
+    result: Value,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct WithContext<T> {
+    value: T,
+}
+
+#[derive(Deserialize)]
+struct UiAccount {
+    data: (String, String),
+}
+
+#[derive(Deserialize)]
+struct KeyedUiAccount {
+    pubkey: String,
+    account: UiAccount,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UiBlockhash {
+    blockhash: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignatureStatus {
+    err: Option<Value>,
+    confirmation_status: Option<String>,
+}
+
+pub struct RpcClient {
+    url: String,
+    http: reqwest::Client,
+    commitment: String,
+}
+
+impl RpcClient {
+    /// A client for `url` reading and confirming at `confirmed` commitment.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::new_with_commitment(url, "confirmed")
+    }
+
+    pub fn new_with_commitment(url: impl Into<String>, commitment: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            http: reqwest::Client::new(),
+            commitment: commitment.into(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    async fn call<T: for<'de> Deserialize<'de>>(&self, method: &str, params: Value) -> Result<T> {
+        let response: Response = self
+            .http
+            .post(&self.url)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.error {
+            return Err(ClientError::Rpc {
+                code: error.code,
+                message: error.message,
+            });
+        }
+        serde_json::from_value(response.result)
+            .map_err(|err| ClientError::InvalidResponse(format!("{method}: {err}")))
+    }
+
+    pub async fn get_slot(&self) -> Result<u64> {
+        self.call("getSlot", json!([{ "commitment": self.commitment }]))
+            .await
+    }
+
+    /// Estimated production time of `slot` in Unix seconds, if the node still has it.
+    pub async fn get_block_time(&self, slot: u64) -> Result<Option<i64>> {
+        self.call("getBlockTime", json!([slot])).await
+    }
+
+    pub async fn get_balance(&self, address: &Pubkey) -> Result<u64> {
+        let balance: WithContext<u64> = self
+            .call(
+                "getBalance",
+                json!([address.to_string(), { "commitment": self.commitment }]),
+            )
+            .await?;
+        Ok(balance.value)
+    }
+
+    /// Raw account data, or `None` if the account does not exist.
+    pub async fn get_account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>> {
+        let account: WithContext<Option<UiAccount>> = self
+            .call(
+                "getAccountInfo",
+                json!([
+                    address.to_string(),
+                    { "encoding": "base64", "commitment": self.commitment },
+                ]),
+            )
+            .await?;
+        account
+            .value
+            .map(|account| decode_data(&account))
+            .transpose()
+    }
+
+    /// Every account owned by `program_id` matching all of `filters`.
+    pub async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[Filter],
+    ) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+        let filters: Vec<Value> = filters.iter().map(Filter::to_json).collect();
+        let accounts: Vec<KeyedUiAccount> = self
+            .call(
+                "getProgramAccounts",
+                json!([
+                    program_id.to_string(),
+                    {
+                        "encoding": "base64",
+                        "commitment": self.commitment,
+                        "filters": filters,
+                    },
+                ]),
+            )
+            .await?;
+        accounts
+            .into_iter()
+            .map(|keyed| {
+                let address = Pubkey::from_str(&keyed.pubkey)
+                    .map_err(|err| ClientError::InvalidResponse(err.to_string()))?;
+                Ok((address, decode_data(&keyed.account)?))
+            })
+            .collect()
+    }
+
+    pub async fn get_latest_blockhash(&self) -> Result<Hash> {
+        let blockhash: WithContext<UiBlockhash> = self
+            .call(
+                "getLatestBlockhash",
+                json!([{ "commitment": self.commitment }]),
+            )
+            .await?;
+        Hash::from_str(&blockhash.value.blockhash)
+            .map_err(|err| ClientError::InvalidResponse(err.to_string()))
+    }
+
+    pub async fn send_transaction(&self, transaction: &Transaction) -> Result<Signature> {
+        let wire = bincode::serialize(transaction)
+            .map_err(|err| ClientError::InvalidResponse(err.to_string()))?;
+        let signature: String = self
+            .call(
+                "sendTransaction",
+                json!([
+                    BASE64_STANDARD.encode(wire),
+                    { "encoding": "base64", "preflightCommitment": self.commitment },
+                ]),
+            )
+            .await?;
+        Signature::from_str(&signature).map_err(|err| ClientError::InvalidResponse(err.to_string()))
+    }
+
+    /// Polls until `signature` reaches the client's commitment, fails, or `timeout` passes.
+    pub async fn confirm_transaction(
+        &self,
+        signature: &Signature,
+        timeout: Duration,
+    ) -> Result<()> {
+        let started = Instant::now();
+        loop {
+            let statuses: WithContext<Vec<Option<SignatureStatus>>> = self
+                .call(
+                    "getSignatureStatuses",
+                    json!([[signature.to_string()], { "searchTransactionHistory": false }]),
+                )
+                .await?;
+            if let Some(Some(status)) = statuses.value.into_iter().next() {
+                if let Some(err) = status.err {
+                    return Err(ClientError::TransactionFailed(*signature, err.to_string()));
+                }
+                if reached(status.confirmation_status.as_deref(), &self.commitment) {
+                    return Ok(());
+                }
+            }
+            if started.elapsed() >= timeout {
+                return Err(ClientError::ConfirmationTimeout(*signature));
+            }
+            tokio::time::sleep(CONFIRM_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Signs `instructions` with `payer` and `signers`, sends them and waits for confirmation.
+    pub async fn send_and_confirm(
+        &self,
+        instructions: &[Instruction],
+        payer: &Keypair,
+        signers: &[&Keypair],
+    ) -> Result<Signature> {
+        let blockhash = self.get_latest_blockhash().await?;
+        let mut all_signers: Vec<&Keypair> = vec![payer];
+        all_signers.extend(
+            signers
+                .iter()
+                .copied()
+                .filter(|s| s.pubkey() != payer.pubkey()),
+        );
+        let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+        transaction.try_sign(&all_signers, blockhash)?;
+        let signature = self.send_transaction(&transaction).await?;
+        self.confirm_transaction(&signature, CONFIRM_TIMEOUT)
+            .await?;
+        Ok(signature)
+    }
+}
+
+fn decode_data(account: &UiAccount) -> Result<Vec<u8>> {
+    let (data, encoding) = &account.data;
+    if encoding != "base64" {
+        return Err(ClientError::InvalidResponse(format!(
+            "expected base64 account data, got {encoding}"
+        )));
+    }
+    BASE64_STANDARD
+        .decode(data)
+        .map_err(|err| ClientError::InvalidResponse(err.to_string()))
+}
+
+/// Whether a transaction at `status` satisfies `commitment`.
+fn reached(status: Option<&str>, commitment: &str) -> bool {
+    let rank = |level: &str| match level {
+        "processed" => 0,
+        "confirmed" => 1,
+        "finalized" => 2,
+        _ => 1,
+    };
+    status.is_some_and(|status| rank(status) >= rank(commitment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memcmp_filters_are_base64_encoded() {
+        let filter = Filter::Memcmp {
+            offset: 41,
+            bytes: vec![1, 2, 3],
+        };
+        assert_eq!(
+            filter.to_json(),
+            json!({ "memcmp": { "offset": 41, "bytes": "AQID", "encoding": "base64" } })
+        );
+    }
+
+    #[test]
+    fn commitment_levels_are_ordered() {
+        assert!(reached(Some("finalized"), "confirmed"));
+        assert!(reached(Some("confirmed"), "confirmed"));
+        assert!(!reached(Some("processed"), "confirmed"));
+        assert!(!reached(None, "processed"));
+    }
+}
@@ -0,0 +1,445 @@
+// breakin/crates/breakin-indexer/src/db.rs
+//! SQLite schema and writes for indexed arena state.
+//!
+//! `matches` holds one row per betting pool with its match result folded in, `bets` one
+//! row per `UserBet`, `payouts` what each claimed bet was paid, `redemptions` every
+//! position token redemption and `users` per-wallet totals over the bets they currently
+//! own. Amounts are lamports. The raw account data is kept so payouts can be estimated
+//! after the pool is swept when the claim's event was missed.
+use std::path::Path;
+
+use breakin_client::anchor_lang::prelude::Pubkey;
+use breakin_client::anchor_lang::AccountDeserialize;
+use breakin_client::program::{
+    BettingPool, ClaimAmounts, ClaimPaid, MarketKind, MatchRecord, PositionRedeemed, UserBet,
+};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Result};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS matches (
+    match_id            INTEGER PRIMARY KEY,
+    address             TEXT,
+    authority           TEXT,
+    status              TEXT,
+    market              TEXT,
+    ai1_odds_bps        INTEGER,
+    ai2_odds_bps        INTEGER,
+    total_ai1_bets      INTEGER NOT NULL DEFAULT 0,
+    total_ai2_bets      INTEGER NOT NULL DEFAULT 0,
+    min_bet_threshold   INTEGER,
+    fee_bps             INTEGER,
+    betting_deadline    TEXT,
+    claim_deadline_slot INTEGER,
+    total_paid_out      INTEGER,
+    ai1_move            TEXT,
+    ai2_move            TEXT,
+    winner              TEXT,
+    overturned          INTEGER,
+    resolved_at         INTEGER,
+    pool_data           BLOB,
+    record_data         BLOB,
+    updated_slot        INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS users (
+    pubkey          TEXT PRIMARY KEY,
+    bet_count       INTEGER NOT NULL,
+    total_wagered   INTEGER NOT NULL,
+    total_paid_out  INTEGER NOT NULL,
+    first_seen_slot INTEGER NOT NULL,
+    last_seen_slot  INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS bets (
+    address         TEXT PRIMARY KEY,
+    match_id        INTEGER NOT NULL,
+    owner           TEXT NOT NULL,
+    origin          TEXT NOT NULL,
+    prediction      TEXT NOT NULL,
+    amount          INTEGER NOT NULL,
+    claimed         INTEGER NOT NULL,
+    locked_odds_bps INTEGER NOT NULL,
+    data            BLOB NOT NULL,
+    updated_slot    INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS bets_by_match ON bets (match_id);
+CREATE INDEX IF NOT EXISTS bets_by_owner ON bets (owner);
+
+CREATE TABLE IF NOT EXISTS payouts (
+    bet_address   TEXT PRIMARY KEY REFERENCES bets (address),
+    match_id      INTEGER NOT NULL,
+    owner         TEXT NOT NULL,
+    payout        INTEGER NOT NULL,
+    fee           INTEGER NOT NULL,
+    stake         INTEGER NOT NULL,
+    bond_share    INTEGER NOT NULL,
+    keeper_reward INTEGER NOT NULL,
+    outcome       TEXT NOT NULL,
+    -- 1 when derived from account state rather than read from the claim's event.
+    estimated     INTEGER NOT NULL,
+    slot          INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS payouts_by_owner ON payouts (owner);
+
+CREATE TABLE IF NOT EXISTS redemptions (
+    signature     TEXT NOT NULL,
+    event_index   INTEGER NOT NULL,
+    match_id      INTEGER NOT NULL,
+    position_mint TEXT NOT NULL,
+    holder        TEXT NOT NULL,
+    side          TEXT NOT NULL,
+    amount        INTEGER NOT NULL,
+    payout        INTEGER NOT NULL,
+    fee           INTEGER NOT NULL,
+    stake         INTEGER NOT NULL,
+    bond_share    INTEGER NOT NULL,
+    slot          INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX IF NOT EXISTS redemptions_by_holder ON redemptions (holder);
+";
+
+pub struct Db {
+    conn: Connection,
+}
+
+impl Db {
+    /// Opens or creates the database at `path` and brings the schema up to date.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        // WAL lets readers such as the API server query while the indexer writes.
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Self::init(conn)
+    }
+
+    /// Opens an existing database without write access.
+    pub fn open_read_only(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        Ok(Self { conn })
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    pub fn upsert_match(
+        &self,
+        address: &Pubkey,
+        pool: &BettingPool,
+        data: &[u8],
+        slot: u64,
+    ) -> Result<()> {
+        let (market, ai1_odds_bps, ai2_odds_bps) = match pool.market {
+            MarketKind::Parimutuel => ("Parimutuel", None, None),
+            MarketKind::FixedOdds {
+                ai1_odds_bps,
+                ai2_odds_bps,
+            } => ("FixedOdds", Some(ai1_odds_bps), Some(ai2_odds_bps)),
+        };
+        self.conn.execute(
+            "INSERT INTO matches (
+                match_id, address, authority, status, market, ai1_odds_bps, ai2_odds_bps,
+                total_ai1_bets, total_ai2_bets, min_bet_threshold, fee_bps, betting_deadline,
+                claim_deadline_slot, total_paid_out, pool_data, updated_slot
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+            ON CONFLICT (match_id) DO UPDATE SET
+                address = excluded.address,
+                authority = excluded.authority,
+                status = excluded.status,
+                market = excluded.market,
+                ai1_odds_bps = excluded.ai1_odds_bps,
+                ai2_odds_bps = excluded.ai2_odds_bps,
+                total_ai1_bets = excluded.total_ai1_bets,
+                total_ai2_bets = excluded.total_ai2_bets,
+                min_bet_threshold = excluded.min_bet_threshold,
+                fee_bps = excluded.fee_bps,
+                betting_deadline = excluded.betting_deadline,
+                claim_deadline_slot = excluded.claim_deadline_slot,
+                total_paid_out = excluded.total_paid_out,
+                pool_data = excluded.pool_data,
+                updated_slot = excluded.updated_slot
+            WHERE excluded.updated_slot >= matches.updated_slot",
+            params![
+                pool.match_id,
+                address.to_string(),
+                pool.authority.to_string(),
+                format!("{:?}", pool.status),
+                market,
+                ai1_odds_bps,
+                ai2_odds_bps,
+                pool.total_ai1_bets,
+                pool.total_ai2_bets,
+                pool.min_bet_threshold,
+                pool.fee_bps,
+                format!("{:?}", pool.betting_deadline),
+                pool.claim_deadline_slot,
+                pool.total_paid_out,
+                data,
+                slot,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Folds a match result into its `matches` row, creating the row if the pool has
+    /// not been seen yet. Records never change the row's `updated_slot`, which tracks
+    /// the pool alone.
+    pub fn upsert_match_record(&self, record: &MatchRecord, data: &[u8]) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO matches (
+                match_id, ai1_move, ai2_move, winner, overturned, resolved_at, record_data,
+                updated_slot
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0)
+            ON CONFLICT (match_id) DO UPDATE SET
+                ai1_move = excluded.ai1_move,
+                ai2_move = excluded.ai2_move,
+                winner = excluded.winner,
+                overturned = excluded.overturned,
+                resolved_at = excluded.resolved_at,
+                record_data = excluded.record_data",
+            params![
+                record.match_id,
+                format!("{:?}", record.ai1_move),
+                format!("{:?}", record.ai2_move),
+                format!("{:?}", record.winner),
+                record.overturned,
+                record.timestamp,
+                data,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Writes `bet` and returns its previous owner if it changed hands.
+    pub fn upsert_bet(
+        &self,
+        address: &Pubkey,
+        bet: &UserBet,
+        data: &[u8],
+        slot: u64,
+    ) -> Result<Option<String>> {
+        let address = address.to_string();
+        let previous_owner: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT owner FROM bets WHERE address = ?1",
+                [&address],
+                |row| row.get(0),
+            )
+            .optional()?;
+        self.conn.execute(
+            "INSERT INTO bets (
+                address, match_id, owner, origin, prediction, amount, claimed, locked_odds_bps,
+                data, updated_slot
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            ON CONFLICT (address) DO UPDATE SET
+                owner = excluded.owner,
+                amount = excluded.amount,
+                claimed = excluded.claimed,
+                locked_odds_bps = excluded.locked_odds_bps,
+                data = excluded.data,
+                updated_slot = excluded.updated_slot
+            WHERE excluded.updated_slot >= bets.updated_slot",
+            params![
+                address,
+                bet.match_id,
+                bet.better.to_string(),
+                bet.origin.to_string(),
+                format!("{:?}", bet.prediction),
+                bet.amount,
+                bet.claimed,
+                bet.locked_odds_bps,
+                data,
+                slot,
+            ],
+        )?;
+        let owner = bet.better.to_string();
+        Ok(previous_owner.filter(|previous| *previous != owner))
+    }
+
+    /// Marks the pool at `address` as swept, if it is one. Returns whether it was.
+    pub fn mark_closed(&self, address: &Pubkey, slot: u64) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE matches SET status = 'Closed', updated_slot = ?2
+             WHERE address = ?1 AND updated_slot <= ?2",
+            params![address.to_string(), slot],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Records an estimate of what a claimed bet was paid. Ignored once the bet has any
+    /// payout, estimated or not.
+    pub fn insert_payout(
+        &self,
+        bet_address: &str,
+        match_id: u64,
+        owner: &str,
+        claim: &ClaimAmounts,
+        slot: u64,
+    ) -> Result<()> {
+        let outcome = claim
+            .outcome
+            .map_or_else(|| "Refunded".to_string(), |winner| format!("{winner:?}"));
+        self.conn.execute(
+            "INSERT OR IGNORE INTO payouts (
+                bet_address, match_id, owner, payout, fee, stake, bond_share, keeper_reward,
+                outcome, estimated, slot
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, ?8, 1, ?9)",
+            params![
+                bet_address,
+                match_id,
+                owner,
+                claim.payout,
+                claim.fee,
+                claim.stake,
+                claim.bond_share,
+                outcome,
+                slot,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Records a payout read from its `ClaimPaid` event, replacing any estimate.
+    pub fn record_claim_paid(&self, event: &ClaimPaid, slot: u64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO payouts (
+                bet_address, match_id, owner, payout, fee, stake, bond_share, keeper_reward,
+                outcome, estimated, slot
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8,
+                COALESCE(
+                    (SELECT winner FROM matches
+                     WHERE match_id = ?2 AND status NOT LIKE 'Cancelled%'),
+                    'Refunded'
+                ),
+                0, ?9)
+            ON CONFLICT (bet_address) DO UPDATE SET
+                owner = excluded.owner,
+                payout = excluded.payout,
+                fee = excluded.fee,
+                stake = excluded.stake,
+                bond_share = excluded.bond_share,
+                keeper_reward = excluded.keeper_reward,
+                estimated = 0,
+                slot = excluded.slot",
+            params![
+                event.user_bet.to_string(),
+                event.match_id,
+                event.recipient.to_string(),
+                event.payout,
+                event.fee,
+                event.stake,
+                event.bond_share,
+                event.keeper_reward,
+                slot,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Records a position token redemption, the `event_index`th event logged by the
+    /// transaction `signature`.
+    pub fn insert_redemption(
+        &self,
+        signature: &str,
+        event_index: usize,
+        event: &PositionRedeemed,
+        slot: u64,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO redemptions (
+                signature, event_index, match_id, position_mint, holder, side, amount, payout,
+                fee, stake, bond_share, slot
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                signature,
+                event_index,
+                event.match_id,
+                event.position_mint.to_string(),
+                event.holder.to_string(),
+                format!("{:?}", event.side),
+                event.amount,
+                event.payout,
+                event.fee,
+                event.stake,
+                event.bond_share,
+                slot,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Claimed bets on `match_id` with no payout recorded yet, as (address, bet).
+    pub fn claimed_without_payout(&self, match_id: u64) -> Result<Vec<(String, UserBet)>> {
+        let mut statement = self.conn.prepare(
+            "SELECT b.address, b.data
+             FROM bets b LEFT JOIN payouts p ON p.bet_address = b.address
+             WHERE b.match_id = ?1 AND b.claimed AND p.bet_address IS NULL",
+        )?;
+        let rows = statement.query_map([match_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let mut bets = Vec::new();
+        for row in rows {
+            let (address, data): (String, Vec<u8>) = row?;
+            if let Some(bet) = decode(Some(data)) {
+                bets.push((address, bet));
+            }
+        }
+        Ok(bets)
+    }
+
+    /// The last indexed state of a pool and its match record.
+    pub fn stored_match(
+        &self,
+        match_id: u64,
+    ) -> Result<(Option<BettingPool>, Option<MatchRecord>)> {
+        let (pool, record) = self
+            .conn
+            .query_row(
+                "SELECT pool_data, record_data FROM matches WHERE match_id = ?1",
+                [match_id],
+                |row| Ok((decode(row.get(0)?), decode(row.get(1)?))),
+            )
+            .optional()?
+            .unwrap_or_default();
+        Ok((pool, record))
+    }
+
+    /// Recomputes `pubkey`'s totals from the bets it owns and the payouts and redemptions
+    /// it received.
+    pub fn refresh_user(&self, pubkey: &str, slot: u64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO users (
+                pubkey, bet_count, total_wagered, total_paid_out, first_seen_slot, last_seen_slot
+            )
+            SELECT ?1,
+                (SELECT COUNT(*) FROM bets WHERE owner = ?1),
+                (SELECT COALESCE(SUM(amount), 0) FROM bets WHERE owner = ?1),
+                (SELECT COALESCE(SUM(payout), 0) FROM payouts WHERE owner = ?1)
+                    + (SELECT COALESCE(SUM(payout), 0) FROM redemptions WHERE holder = ?1),
+                ?2, ?2
+            ON CONFLICT (pubkey) DO UPDATE SET
+                bet_count = excluded.bet_count,
+                total_wagered = excluded.total_wagered,
+                total_paid_out = excluded.total_paid_out,
+                last_seen_slot = MAX(users.last_seen_slot, excluded.last_seen_slot)",
+            params![pubkey, slot],
+        )?;
+        Ok(())
+    }
+}
+
+fn decode<T: AccountDeserialize>(data: Option<Vec<u8>>) -> Option<T> {
+    data.and_then(|data| T::try_deserialize(&mut &data[..]).ok())
+}
@@ -0,0 +1,350 @@
+// breakin/crates/breakin-indexer/src/ingest.rs
+//! Decodes program accounts and events and applies them to the database, whether they
+//! come from a `getProgramAccounts` backfill or the websocket stream.
+//!
+//! Exact payouts come from the `ClaimPaid` and `PositionRedeemed` events the program
+//! logs. Events are not replayed on backfill, so a bet seen claimed without its event
+//! gets a payout estimated from the pool and match record, flagged as such. Estimates
+//! use the pro-rata share and miss the rounding dust the last winning claim receives.
+use anyhow::Result;
+use breakin_client::anchor_lang::prelude::Pubkey;
+use breakin_client::anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator, Space};
+use breakin_client::estimate::claimable;
+use breakin_client::program::{BettingPool, ClaimPaid, MatchRecord, PositionRedeemed, UserBet};
+use breakin_client::rpc::Filter;
+use breakin_client::{RpcClient, PROGRAM_ID};
+use log::{debug, info};
+
+use crate::db::Db;
+
+/// An account of a type the indexer stores.
+pub enum Indexed {
+    Pool(Box<BettingPool>),
+    Record(MatchRecord),
+    Bet(UserBet),
+}
+
+fn is<T: Discriminator + Space>(data: &[u8]) -> bool {
+    data.len() == 8 + T::INIT_SPACE && data.starts_with(T::DISCRIMINATOR)
+}
+
+fn parse<T: AccountDeserialize>(data: &[u8]) -> Option<T> {
+    T::try_deserialize(&mut &data[..]).ok()
+}
+
+/// Decodes `data` if it is a current-layout pool, match record or bet.
+pub fn decode(data: &[u8]) -> Option<Indexed> {
+    if is::<BettingPool>(data) {
+        parse(data).map(|pool| Indexed::Pool(Box::new(pool)))
+    } else if is::<MatchRecord>(data) {
+        parse(data).map(Indexed::Record)
+    } else if is::<UserBet>(data) {
+        parse(data).map(Indexed::Bet)
+    } else {
+        None
+    }
+}
+
+/// An event the indexer stores.
+pub enum Event {
+    ClaimPaid(ClaimPaid),
+    PositionRedeemed(PositionRedeemed),
+}
+
+/// Decodes the data of an Anchor event log, a discriminator followed by the event.
+pub fn decode_event(data: &[u8]) -> Option<Event> {
+    if let Some(mut rest) = data.strip_prefix(ClaimPaid::DISCRIMINATOR) {
+        ClaimPaid::deserialize(&mut rest).ok().map(Event::ClaimPaid)
+    } else if let Some(mut rest) = data.strip_prefix(PositionRedeemed::DISCRIMINATOR) {
+        PositionRedeemed::deserialize(&mut rest)
+            .ok()
+            .map(Event::PositionRedeemed)
+    } else {
+        None
+    }
+}
+
+pub struct Indexer {
+    db: Db,
+}
+
+impl Indexer {
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+
+    pub fn db(&self) -> &Db {
+        &self.db
+    }
+
+    /// Applies the state of the account at `address` as of `slot`. Empty data means the
+    /// account was closed.
+    pub fn apply(&self, address: &Pubkey, data: &[u8], slot: u64) -> Result<()> {
+        if data.is_empty() {
+            if self.db.mark_closed(address, slot)? {
+                info!("Pool {address} closed");
+            }
+            return Ok(());
+        }
+        match decode(data) {
+            Some(Indexed::Pool(pool)) => {
+                self.db.upsert_match(address, &pool, data, slot)?;
+                self.derive_payouts(pool.match_id, slot)?;
+            }
+            Some(Indexed::Record(record)) => {
+                self.db.upsert_match_record(&record, data)?;
+                self.derive_payouts(record.match_id, slot)?;
+            }
+            Some(Indexed::Bet(bet)) => {
+                let previous_owner = self.db.upsert_bet(address, &bet, data, slot)?;
+                if bet.claimed {
+                    self.derive_payouts(bet.match_id, slot)?;
+                }
+                self.db.refresh_user(&bet.better.to_string(), slot)?;
+                if let Some(previous_owner) = previous_owner {
+                    self.db.refresh_user(&previous_owner, slot)?;
+                }
+            }
+            None => debug!("Skipping {address}: not an indexed account"),
+        }
+        Ok(())
+    }
+
+    /// Applies the events logged by the transaction `signature`, in log order.
+    pub fn apply_events(&self, signature: &str, events: &[Event], slot: u64) -> Result<()> {
+        for (index, event) in events.iter().enumerate() {
+            let owner = match event {
+                Event::ClaimPaid(event) => {
+                    self.db.record_claim_paid(event, slot)?;
+                    event.recipient
+                }
+                Event::PositionRedeemed(event) => {
+                    self.db.insert_redemption(signature, index, event, slot)?;
+                    event.holder
+                }
+            };
+            self.db.refresh_user(&owner.to_string(), slot)?;
+        }
+        Ok(())
+    }
+
+    /// Estimates payouts for claimed bets on `match_id` that do not have one yet. Bets
+    /// whose pool or match record has not been indexed are picked up when it arrives.
+    fn derive_payouts(&self, match_id: u64, slot: u64) -> Result<()> {
+        let (Some(pool), record) = self.db.stored_match(match_id)? else {
+            return Ok(());
+        };
+        // Evaluate every claim as if it were the first, so the result does not depend on
+        // how many claims the stored pool had already seen.
+        let pool = BettingPool {
+            total_paid_out: 0,
+            claimed_count: 0,
+            claimed_stake: 0,
+            fees_accrued: 0,
+            ..pool
+        };
+        for (address, bet) in self.db.claimed_without_payout(match_id)? {
+            match claimable(&pool, record.as_ref(), &bet) {
+                Ok(claim) => {
+                    let owner = bet.better.to_string();
+                    self.db
+                        .insert_payout(&address, match_id, &owner, &claim, slot)?;
+                    self.db.refresh_user(&owner, slot)?;
+                }
+                Err(err) => debug!("Match #{match_id}: no payout for {address} yet: {err}"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads every current pool, match record and bet from `getProgramAccounts`.
+    pub async fn backfill(&self, rpc: &RpcClient) -> Result<()> {
+        let slot = rpc.get_slot().await?;
+        let mut count = 0;
+        for discriminator in [
+            BettingPool::DISCRIMINATOR,
+            MatchRecord::DISCRIMINATOR,
+            UserBet::DISCRIMINATOR,
+        ] {
+            let filters = [Filter::Memcmp {
+                offset: 0,
+                bytes: discriminator.to_vec(),
+            }];
+            for (address, data) in rpc.get_program_accounts(&PROGRAM_ID, &filters).await? {
+                self.apply(&address, &data, slot)?;
+                count += 1;
+            }
+        }
+        info!("Backfilled {count} accounts at slot {slot}");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use breakin_client::anchor_lang::AccountSerialize;
+    use breakin_client::program::{MatchStatus, Move, Prediction, Winner};
+
+    /// Account data as the program allocates it, padded to the full size.
+    fn bytes<T: AccountSerialize + Space>(account: &T) -> Vec<u8> {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data.resize(8 + T::INIT_SPACE, 0);
+        data
+    }
+
+    fn settled_pool(match_id: u64) -> BettingPool {
+        BettingPool {
+            match_id,
+            status: MatchStatus::Settled,
+            total_ai1_bets: 3_000,
+            total_ai2_bets: 1_000,
+            // Claim progress from earlier claims must not change derived payouts.
+            total_paid_out: 2_000,
+            claimed_stake: 2_000,
+            claimed_count: 1,
+            ..Default::default()
+        }
+    }
+
+    fn record(match_id: u64) -> MatchRecord {
+        MatchRecord {
+            match_id,
+            ai1_move: Move::Rock,
+            ai2_move: Move::Scissors,
+            winner: Winner::Ai1,
+            ..Default::default()
+        }
+    }
+
+    fn bet(match_id: u64, better: Pubkey, claimed: bool) -> UserBet {
+        UserBet {
+            better,
+            origin: better,
+            match_id,
+            prediction: Prediction::Ai1,
+            amount: 1_000,
+            claimed,
+            ..Default::default()
+        }
+    }
+
+    fn count(indexer: &Indexer, sql: &str) -> i64 {
+        indexer
+            .db()
+            .connection()
+            .query_row(sql, [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn decodes_by_discriminator_and_size() {
+        let pool = settled_pool(7);
+        assert!(matches!(decode(&bytes(&pool)), Some(Indexed::Pool(p)) if p.match_id == 7));
+        assert!(matches!(
+            decode(&bytes(&record(7))),
+            Some(Indexed::Record(_))
+        ));
+        let mut short = bytes(&pool);
+        short.truncate(short.len() - 1);
+        assert!(decode(&short).is_none());
+        assert!(decode(&[]).is_none());
+    }
+
+    #[test]
+    fn derives_payouts_in_any_order() {
+        let indexer = Indexer::new(Db::open_in_memory().unwrap());
+        let alice = Pubkey::new_unique();
+        let bet_address = Pubkey::new_unique();
+        // The claimed bet arrives before the pool and record it needs.
+        indexer
+            .apply(&bet_address, &bytes(&bet(7, alice, true)), 10)
+            .unwrap();
+        assert_eq!(count(&indexer, "SELECT COUNT(*) FROM payouts"), 0);
+
+        indexer
+            .apply(&Pubkey::new_unique(), &bytes(&settled_pool(7)), 11)
+            .unwrap();
+        assert_eq!(count(&indexer, "SELECT COUNT(*) FROM payouts"), 0);
+        indexer
+            .apply(&Pubkey::new_unique(), &bytes(&record(7)), 12)
+            .unwrap();
+
+        // 1/3 of the 1_000 lamports on AI 2, on top of the stake.
+        assert_eq!(count(&indexer, "SELECT payout FROM payouts"), 1_333);
+        assert_eq!(count(&indexer, "SELECT total_paid_out FROM users"), 1_333);
+        assert_eq!(count(&indexer, "SELECT winner = 'Ai1' FROM matches"), 1);
+    }
+
+    #[test]
+    fn events_replace_estimated_payouts() {
+        let indexer = Indexer::new(Db::open_in_memory().unwrap());
+        let alice = Pubkey::new_unique();
+        let bet_address = Pubkey::new_unique();
+        indexer
+            .apply(&Pubkey::new_unique(), &bytes(&settled_pool(7)), 10)
+            .unwrap();
+        indexer
+            .apply(&Pubkey::new_unique(), &bytes(&record(7)), 10)
+            .unwrap();
+        indexer
+            .apply(&bet_address, &bytes(&bet(7, alice, true)), 11)
+            .unwrap();
+        assert_eq!(count(&indexer, "SELECT estimated FROM payouts"), 1);
+
+        // The last claim also took the dust the estimate leaves out.
+        let paid = ClaimPaid {
+            match_id: 7,
+            user_bet: bet_address,
+            recipient: alice,
+            payout: 1_334,
+            fee: 0,
+            stake: 1_000,
+            bond_share: 0,
+            keeper_reward: 5_000,
+        };
+        indexer
+            .apply_events("sig", &[Event::ClaimPaid(paid)], 12)
+            .unwrap();
+        assert_eq!(count(&indexer, "SELECT payout FROM payouts"), 1_334);
+        assert_eq!(count(&indexer, "SELECT estimated FROM payouts"), 0);
+        assert_eq!(count(&indexer, "SELECT keeper_reward FROM payouts"), 5_000);
+        assert_eq!(count(&indexer, "SELECT outcome = 'Ai1' FROM payouts"), 1);
+        assert_eq!(count(&indexer, "SELECT total_paid_out FROM users"), 1_334);
+
+        // Later account updates leave the exact payout alone.
+        indexer
+            .apply(&bet_address, &bytes(&bet(7, alice, true)), 13)
+            .unwrap();
+        assert_eq!(count(&indexer, "SELECT payout FROM payouts"), 1_334);
+    }
+
+    #[test]
+    fn transfers_update_both_owners() {
+        let indexer = Indexer::new(Db::open_in_memory().unwrap());
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let address = Pubkey::new_unique();
+        indexer
+            .apply(&address, &bytes(&bet(7, alice, false)), 10)
+            .unwrap();
+        let mut sold = bet(7, bob, false);
+        sold.origin = alice;
+        indexer.apply(&address, &bytes(&sold), 11).unwrap();
+
+        let bets_of = |owner: Pubkey| {
+            count(
+                &indexer,
+                &format!("SELECT bet_count FROM users WHERE pubkey = '{owner}'"),
+            )
+        };
+        assert_eq!(bets_of(alice), 0);
+        assert_eq!(bets_of(bob), 1);
+
+        // A stale update must not move the bet back.
+        indexer
+            .apply(&address, &bytes(&bet(7, alice, false)), 10)
+            .unwrap();
+        assert_eq!(bets_of(bob), 1);
+    }
+}
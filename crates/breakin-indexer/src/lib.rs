@@ -0,0 +1,11 @@
+// breakin/crates/breakin-indexer/src/lib.rs
+//! Indexes the arena program's betting pools, match records and bets into SQLite.
+//!
+//! [`db`] owns the schema and is shared with readers such as the API server; [`ingest`]
+//! decodes accounts into it and [`stream`] keeps it current over the RPC websocket.
+pub mod db;
+pub mod ingest;
+pub mod stream;
+
+pub use db::Db;
+pub use ingest::Indexer;
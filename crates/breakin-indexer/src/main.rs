@@ -0,0 +1,80 @@
+// breakin/crates/breakin-indexer/src/main.rs
+//! Indexer for the arena program. `backfill` loads every current pool, match record and
+//! bet from `getProgramAccounts` once; `run` does the same and then follows the program
+//! over the RPC websocket, reconnecting and backfilling again whenever the stream drops.
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use breakin_client::RpcClient;
+use breakin_indexer::{stream, Db, Indexer};
+use clap::{Parser, Subcommand};
+use log::{error, info};
+
+/// Longest wait between reconnection attempts.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Parser)]
+#[command(name = "breakin-indexer", version, about)]
+struct Args {
+    /// SQLite database to write. Created if missing.
+    #[arg(long, env = "BREAKIN_DB", default_value = "breakin.db")]
+    db: PathBuf,
+    /// RPC URL. Defaults to a local validator.
+    #[arg(
+        long,
+        short = 'u',
+        env = "BREAKIN_RPC_URL",
+        default_value = "http://127.0.0.1:8899"
+    )]
+    url: String,
+    /// Websocket URL. Derived from --url when omitted.
+    #[arg(long, env = "BREAKIN_WS_URL")]
+    ws_url: Option<String>,
+    /// Commitment used for reads and the subscription.
+    #[arg(long, default_value = "confirmed")]
+    commitment: String,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Load the current state of every indexed account and exit.
+    Backfill,
+    /// Backfill, then follow live updates until interrupted.
+    Run,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let args = Args::parse();
+
+    let indexer = Indexer::new(Db::open(&args.db)?);
+    let rpc = RpcClient::new_with_commitment(args.url.clone(), args.commitment.clone());
+    info!("Indexing {} into {}", args.url, args.db.display());
+
+    match args.command {
+        Command::Backfill => indexer.backfill(&rpc).await,
+        Command::Run => {
+            let ws_url = args.ws_url.unwrap_or_else(|| stream::ws_url(&args.url));
+            let mut delay = Duration::from_secs(1);
+            loop {
+                tokio::select! {
+                    result = stream::run(&indexer, &rpc, &ws_url, &args.commitment) => {
+                        if let Err(err) = result {
+                            error!("Stream failed: {err:#}; reconnecting in {delay:?}");
+                        }
+                    }
+                    _ = tokio::signal::ctrl_c() => {
+                        info!("Shutting down");
+                        return Ok(());
+                    }
+                }
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+        }
+    }
+}
@@ -0,0 +1,334 @@
+// breakin/crates/breakin-indexer/src/stream.rs
+//! Live updates over the RPC websocket. `programSubscribe` pushes the full account
+//! every time the program writes it, so each notification is applied like a backfilled
+//! account. `logsSubscribe` delivers the logs of every transaction that touches the
+//! program, from which the payout events are read.
+use anyhow::{bail, Context, Result};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use breakin_client::{RpcClient, PROGRAM_ID};
+use futures_util::{SinkExt, StreamExt};
+use log::info;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::ingest::{decode_event, Event, Indexer};
+
+/// Request ids of the two subscriptions, used to tell their confirmations apart.
+const ACCOUNTS_SUBSCRIPTION: u64 = 1;
+const LOGS_SUBSCRIPTION: u64 = 2;
+
+/// The websocket endpoint that pairs with the HTTP endpoint `url`: same host, `ws`
+/// scheme, and the validator's default port 8900 in place of 8899.
+pub fn ws_url(url: &str) -> String {
+    let url = if let Some(rest) = url.strip_prefix("https://") {
+        format!("wss://{rest}")
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        format!("ws://{rest}")
+    } else {
+        url.to_string()
+    };
+    url.replacen(":8899", ":8900", 1)
+}
+
+#[derive(Deserialize)]
+struct Incoming {
+    id: Option<u64>,
+    method: Option<String>,
+    params: Option<Params>,
+    result: Option<Value>,
+    error: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct Params {
+    result: Value,
+}
+
+#[derive(Deserialize)]
+struct Update<T> {
+    context: UpdateContext,
+    value: T,
+}
+
+#[derive(Deserialize)]
+struct UpdateContext {
+    slot: u64,
+}
+
+#[derive(Deserialize)]
+struct KeyedAccount {
+    pubkey: String,
+    account: Account,
+}
+
+#[derive(Deserialize)]
+struct Account {
+    /// `[data, encoding]`.
+    data: (String, String),
+    lamports: u64,
+}
+
+#[derive(Deserialize)]
+struct Logs {
+    signature: String,
+    err: Option<Value>,
+    logs: Vec<String>,
+}
+
+/// Events logged by the program itself in a transaction's `logs`. The invocation stack
+/// is tracked so another program cannot forge an event by logging the same bytes.
+pub fn program_events(logs: &[String]) -> Vec<Event> {
+    let program = PROGRAM_ID.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = rest.strip_prefix("data: ") {
+            if stack.last() == Some(&program.as_str()) {
+                let event = BASE64_STANDARD
+                    .decode(data)
+                    .ok()
+                    .and_then(|data| decode_event(&data));
+                events.extend(event);
+            }
+        } else if let Some((id, status)) = rest.split_once(' ') {
+            if status.starts_with("invoke [") {
+                stack.push(id);
+            } else if status == "success" || status.starts_with("failed") {
+                stack.pop();
+            }
+        }
+    }
+    events
+}
+
+/// Applies one websocket message. Subscription confirmations are only logged.
+pub fn handle_message(indexer: &Indexer, text: &str) -> Result<()> {
+    let incoming: Incoming = serde_json::from_str(text).context("decoding notification")?;
+    if let Some(error) = incoming.error {
+        bail!("subscription failed: {error}");
+    }
+    if let Some(subscription) = incoming.result {
+        let what = match incoming.id {
+            Some(LOGS_SUBSCRIPTION) => "program logs",
+            _ => "program accounts",
+        };
+        info!("Subscribed to {what} (subscription {subscription})");
+        return Ok(());
+    }
+    let (Some(method), Some(params)) = (incoming.method.as_deref(), incoming.params) else {
+        return Ok(());
+    };
+    match method {
+        "programNotification" => apply_account(indexer, serde_json::from_value(params.result)?),
+        "logsNotification" => {
+            let update: Update<Logs> = serde_json::from_value(params.result)?;
+            // A failed transaction's events were rolled back with it.
+            if update.value.err.is_some() {
+                return Ok(());
+            }
+            let events = program_events(&update.value.logs);
+            indexer.apply_events(&update.value.signature, &events, update.context.slot)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn apply_account(indexer: &Indexer, update: Update<KeyedAccount>) -> Result<()> {
+    let address = update
+        .value
+        .pubkey
+        .parse()
+        .context("notification for an invalid address")?;
+    let account = update.value.account;
+    let data = if account.lamports == 0 {
+        Vec::new()
+    } else {
+        BASE64_STANDARD
+            .decode(&account.data.0)
+            .context("notification with invalid base64 data")?
+    };
+    indexer.apply(&address, &data, update.context.slot)
+}
+
+/// Subscribes to the program's accounts and logs, backfills so no account written before
+/// the subscription is missed, then applies updates until the connection drops or a
+/// message cannot be applied. Either way the caller reconnects and backfills again;
+/// claims whose events were missed meanwhile keep estimated payouts.
+pub async fn run(indexer: &Indexer, rpc: &RpcClient, ws_url: &str, commitment: &str) -> Result<()> {
+    let (mut socket, _) = connect_async(ws_url)
+        .await
+        .with_context(|| format!("connecting to {ws_url}"))?;
+    let subscribe = json!({
+        "jsonrpc": "2.0",
+        "id": ACCOUNTS_SUBSCRIPTION,
+        "method": "programSubscribe",
+        "params": [
+            PROGRAM_ID.to_string(),
+            { "encoding": "base64", "commitment": commitment },
+        ],
+    });
+    socket.send(Message::text(subscribe.to_string())).await?;
+    let subscribe_logs = json!({
+        "jsonrpc": "2.0",
+        "id": LOGS_SUBSCRIPTION,
+        "method": "logsSubscribe",
+        "params": [
+            { "mentions": [PROGRAM_ID.to_string()] },
+            { "commitment": commitment },
+        ],
+    });
+    socket
+        .send(Message::text(subscribe_logs.to_string()))
+        .await?;
+    indexer.backfill(rpc).await?;
+
+    while let Some(message) = socket.next().await {
+        match message? {
+            Message::Text(text) => handle_message(indexer, text.as_str())?,
+            Message::Close(frame) => bail!("websocket closed: {frame:?}"),
+            _ => {}
+        }
+    }
+    bail!("websocket ended")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use breakin_client::anchor_lang::prelude::Pubkey;
+    use breakin_client::anchor_lang::{AccountSerialize, Event as _, Space};
+    use breakin_client::program::{BettingPool, PositionRedeemed, Prediction};
+
+    #[test]
+    fn derives_ws_url() {
+        assert_eq!(ws_url("http://127.0.0.1:8899"), "ws://127.0.0.1:8900");
+        assert_eq!(
+            ws_url("https://api.devnet.solana.com"),
+            "wss://api.devnet.solana.com"
+        );
+    }
+
+    #[test]
+    fn applies_program_notifications() {
+        let indexer = Indexer::new(Db::open_in_memory().unwrap());
+        let pool = BettingPool {
+            match_id: 3,
+            total_ai1_bets: 500,
+            ..Default::default()
+        };
+        let mut data = Vec::new();
+        pool.try_serialize(&mut data).unwrap();
+        data.resize(8 + BettingPool::INIT_SPACE, 0);
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "programNotification",
+            "params": {
+                "result": {
+                    "context": { "slot": 42 },
+                    "value": {
+                        "pubkey": Pubkey::new_unique().to_string(),
+                        "account": {
+                            "data": [BASE64_STANDARD.encode(&data), "base64"],
+                            "executable": false,
+                            "lamports": 1_000_000,
+                            "owner": PROGRAM_ID.to_string(),
+                            "rentEpoch": 0,
+                            "space": data.len(),
+                        },
+                    },
+                },
+                "subscription": 1,
+            },
+        });
+        handle_message(&indexer, &notification.to_string()).unwrap();
+        let (total, slot): (u64, u64) = indexer
+            .db()
+            .connection()
+            .query_row(
+                "SELECT total_ai1_bets, updated_slot FROM matches WHERE match_id = 3",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((total, slot), (500, 42));
+
+        let error = r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32602,"message":"bad"}}"#;
+        assert!(handle_message(&indexer, error).is_err());
+    }
+
+    fn logs_notification(logs: &[String], err: Value) -> String {
+        json!({
+            "jsonrpc": "2.0",
+            "method": "logsNotification",
+            "params": {
+                "result": {
+                    "context": { "slot": 50 },
+                    "value": { "signature": "sig", "err": err, "logs": logs },
+                },
+                "subscription": 2,
+            },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn reads_only_events_the_program_logged() {
+        let indexer = Indexer::new(Db::open_in_memory().unwrap());
+        let redeemed = PositionRedeemed {
+            match_id: 4,
+            position_mint: Pubkey::new_unique(),
+            holder: Pubkey::new_unique(),
+            side: Prediction::Ai1,
+            amount: 500,
+            payout: 900,
+            fee: 0,
+            stake: 500,
+            bond_share: 0,
+        };
+        let data = format!("Program data: {}", BASE64_STANDARD.encode(redeemed.data()));
+        let other = Pubkey::new_unique();
+        let logs = [
+            format!("Program {PROGRAM_ID} invoke [1]"),
+            data.clone(),
+            format!("Program {other} invoke [2]"),
+            // Forged by a program called from ours.
+            data.clone(),
+            format!("Program {other} success"),
+            format!("Program {PROGRAM_ID} consumed 5000 of 200000 compute units"),
+            format!("Program {PROGRAM_ID} success"),
+            // Logged outside any invocation of ours.
+            data,
+        ];
+        assert_eq!(program_events(&logs).len(), 1);
+
+        let failed = logs_notification(&logs, json!({ "InstructionError": [0, "Custom"] }));
+        handle_message(&indexer, &failed).unwrap();
+        let redemptions = || -> i64 {
+            indexer
+                .db()
+                .connection()
+                .query_row("SELECT COUNT(*) FROM redemptions", [], |row| row.get(0))
+                .unwrap()
+        };
+        assert_eq!(redemptions(), 0);
+
+        handle_message(&indexer, &logs_notification(&logs, Value::Null)).unwrap();
+        assert_eq!(redemptions(), 1);
+        let paid: u64 = indexer
+            .db()
+            .connection()
+            .query_row(
+                "SELECT total_paid_out FROM users WHERE pubkey = ?1",
+                [redeemed.holder.to_string()],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(paid, 900);
+    }
+}
@@ -0,0 +1,383 @@
+// breakin/crates/breakin-keeper/src/keeper.rs
+//! One pass over every live betting pool: work out which lifecycle instruction is due,
+//! if any, and send it with retries.
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use breakin_client::anchor_lang::prelude::{Clock, Pubkey};
+use breakin_client::anchor_lang::solana_program::instruction::Instruction;
+use breakin_client::program::{BettingPool, MatchStatus, Move};
+use breakin_client::{fetch, instructions, RpcClient};
+use log::{debug, info, warn};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use crate::strategy::Strategy;
+
+/// A lifecycle step the keeper can take for a pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// `open_betting` on a scheduled match whose opening time has passed.
+    OpenBetting,
+    /// `check_betting_deadline` once betting has closed.
+    CloseBetting,
+    /// `resolve_match` with moves from the configured strategies.
+    Resolve,
+    /// `finalize_match` once the dispute window has closed.
+    Finalize,
+    /// `cancel_unresolved_match` when another resolver missed its deadline.
+    CancelNoShow,
+}
+
+/// What is due for `pool` at `clock`. Only pools whose authority is `keeper` can be
+/// resolved by it; other resolvers' matches are cancelled once they time out, if
+/// `cancel_no_shows` is set.
+pub fn next_action(
+    pool: &BettingPool,
+    clock: &Clock,
+    keeper: &Pubkey,
+    cancel_no_shows: bool,
+) -> Option<Action> {
+    match pool.status {
+        // check_betting_deadline also closes scheduled matches that never opened.
+        MatchStatus::Scheduled | MatchStatus::OpenForBetting
+            if pool.betting_deadline.has_passed(clock) =>
+        {
+            Some(Action::CloseBetting)
+        }
+        MatchStatus::Scheduled if pool.betting_opens_at.has_passed(clock) => {
+            Some(Action::OpenBetting)
+        }
+        MatchStatus::AwaitingResolution if pool.authority == *keeper => Some(Action::Resolve),
+        MatchStatus::AwaitingResolution
+            if cancel_no_shows && clock.slot >= pool.resolution_deadline_slot =>
+        {
+            Some(Action::CancelNoShow)
+        }
+        MatchStatus::Resolved if clock.slot >= pool.challenge_deadline_slot => {
+            Some(Action::Finalize)
+        }
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub initial_delay: Duration,
+}
+
+/// Runs `op` up to `policy.attempts` times, doubling the delay after each failure.
+pub async fn with_retries<T, F, Fut>(policy: RetryPolicy, what: &str, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut delay = policy.initial_delay;
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < policy.attempts => {
+                warn!(
+                    "{what} failed (attempt {attempt}/{}): {err:#}",
+                    policy.attempts
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Moves the keeper has played, and those chosen for matches it is still resolving.
+#[derive(Default)]
+struct MoveLog {
+    /// Moves chosen for matches still being resolved, so a retry replays the same moves.
+    pending: HashMap<u64, (Move, Move)>,
+    ai1_history: Vec<Move>,
+    ai2_history: Vec<Move>,
+}
+
+impl MoveLog {
+    /// Retires the pending moves for `match_id` once its pool has left
+    /// AwaitingResolution. A resolved match keeps them in the history, since only this
+    /// keeper could have resolved it; a cancelled one never used them.
+    fn retire(&mut self, match_id: u64, status: MatchStatus) {
+        if status == MatchStatus::AwaitingResolution {
+            return;
+        }
+        let Some((ai1_move, ai2_move)) = self.pending.remove(&match_id) else {
+            return;
+        };
+        if matches!(
+            status,
+            MatchStatus::Resolved | MatchStatus::Disputed | MatchStatus::Settled
+        ) {
+            self.ai1_history.push(ai1_move);
+            self.ai2_history.push(ai2_move);
+        }
+    }
+}
+
+pub struct Keeper {
+    rpc: RpcClient,
+    signer: Keypair,
+    ai1: Box<dyn Strategy>,
+    ai2: Box<dyn Strategy>,
+    retry: RetryPolicy,
+    cancel_no_shows: bool,
+    moves: MoveLog,
+}
+
+impl Keeper {
+    pub fn new(
+        rpc: RpcClient,
+        signer: Keypair,
+        ai1: Box<dyn Strategy>,
+        ai2: Box<dyn Strategy>,
+        retry: RetryPolicy,
+        cancel_no_shows: bool,
+    ) -> Self {
+        Self {
+            rpc,
+            signer,
+            ai1,
+            ai2,
+            retry,
+            cancel_no_shows,
+            moves: MoveLog::default(),
+        }
+    }
+
+    /// The cluster's view of now. Block times lag a little behind; Unix deadlines are
+    /// only acted on once the chain agrees they have passed.
+    async fn clock(&self) -> Result<Clock> {
+        let slot = self.rpc.get_slot().await?;
+        let unix_timestamp = match self.rpc.get_block_time(slot).await {
+            Ok(Some(time)) => time,
+            _ => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64,
+        };
+        Ok(Clock {
+            slot,
+            unix_timestamp,
+            ..Default::default()
+        })
+    }
+
+    /// Processes every pool once. Failures are logged and retried on the next tick.
+    pub async fn tick(&mut self) -> Result<()> {
+        let clock = self.clock().await?;
+        let mut pools = fetch::betting_pools(&self.rpc).await?;
+        pools.sort_by_key(|(_, pool)| pool.match_id);
+        let me = self.signer.pubkey();
+        for (_, pool) in pools {
+            // A resolve whose confirmation timed out may still have landed.
+            self.moves.retire(pool.match_id, pool.status);
+            let Some(action) = next_action(&pool, &clock, &me, self.cancel_no_shows) else {
+                continue;
+            };
+            let match_id = pool.match_id;
+            if let Err(err) = self.run(action, match_id).await {
+                warn!("Match #{match_id}: {action:?} gave up: {err:#}");
+            }
+        }
+        Ok(())
+    }
+
+    async fn run(&mut self, action: Action, match_id: u64) -> Result<()> {
+        let instruction = match action {
+            Action::OpenBetting => instructions::open_betting(match_id),
+            Action::CloseBetting => instructions::check_betting_deadline(match_id),
+            Action::Finalize => instructions::finalize_match(match_id),
+            Action::CancelNoShow => instructions::cancel_unresolved_match(match_id),
+            Action::Resolve => {
+                let (ai1_move, ai2_move) = self.moves_for(match_id);
+                info!("Match #{match_id}: AI 1 plays {ai1_move:?}, AI 2 plays {ai2_move:?}");
+                instructions::resolve_match(&self.signer.pubkey(), match_id, ai1_move, ai2_move)
+            }
+        };
+        self.send(&instruction, &format!("Match #{match_id}: {action:?}"))
+            .await?;
+
+        if action == Action::Resolve {
+            self.moves.retire(match_id, MatchStatus::Resolved);
+        }
+        Ok(())
+    }
+
+    fn moves_for(&mut self, match_id: u64) -> (Move, Move) {
+        let (ai1, ai2) = (&mut self.ai1, &mut self.ai2);
+        let (ai1_history, ai2_history) = (&self.moves.ai1_history, &self.moves.ai2_history);
+        *self.moves.pending.entry(match_id).or_insert_with(|| {
+            (
+                ai1.next_move(match_id, ai2_history),
+                ai2.next_move(match_id, ai1_history),
+            )
+        })
+    }
+
+    async fn send(&self, instruction: &Instruction, what: &str) -> Result<()> {
+        let signature = with_retries(self.retry, what, || async {
+            Ok(self
+                .rpc
+                .send_and_confirm(std::slice::from_ref(instruction), &self.signer, &[])
+                .await?)
+        })
+        .await?;
+        info!("{what} confirmed: {signature}");
+        debug!("{what}: {instruction:?}");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use breakin_client::program::BettingTime;
+
+    fn clock(slot: u64) -> Clock {
+        Clock {
+            slot,
+            unix_timestamp: 1_700_000_000,
+            ..Default::default()
+        }
+    }
+
+    fn pool(status: MatchStatus, authority: Pubkey) -> BettingPool {
+        BettingPool {
+            status,
+            authority,
+            betting_opens_at: BettingTime::Slot(100),
+            betting_deadline: BettingTime::Slot(200),
+            resolution_deadline_slot: 300,
+            challenge_deadline_slot: 400,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn follows_the_betting_timeline() {
+        let me = Pubkey::new_unique();
+        let scheduled = pool(MatchStatus::Scheduled, me);
+        assert_eq!(next_action(&scheduled, &clock(50), &me, true), None);
+        assert_eq!(
+            next_action(&scheduled, &clock(100), &me, true),
+            Some(Action::OpenBetting)
+        );
+        assert_eq!(
+            next_action(&scheduled, &clock(200), &me, true),
+            Some(Action::CloseBetting)
+        );
+
+        let open = pool(MatchStatus::OpenForBetting, me);
+        assert_eq!(next_action(&open, &clock(199), &me, true), None);
+        assert_eq!(
+            next_action(&open, &clock(200), &me, true),
+            Some(Action::CloseBetting)
+        );
+    }
+
+    #[test]
+    fn unix_deadlines_use_cluster_time() {
+        let me = Pubkey::new_unique();
+        let open = BettingPool {
+            betting_deadline: BettingTime::UnixTimestamp(1_700_000_001),
+            ..pool(MatchStatus::OpenForBetting, me)
+        };
+        assert_eq!(next_action(&open, &clock(u64::MAX), &me, true), None);
+    }
+
+    #[test]
+    fn only_resolves_own_matches() {
+        let me = Pubkey::new_unique();
+        let mine = pool(MatchStatus::AwaitingResolution, me);
+        assert_eq!(
+            next_action(&mine, &clock(0), &me, true),
+            Some(Action::Resolve)
+        );
+
+        let theirs = pool(MatchStatus::AwaitingResolution, Pubkey::new_unique());
+        assert_eq!(next_action(&theirs, &clock(299), &me, true), None);
+        assert_eq!(
+            next_action(&theirs, &clock(300), &me, true),
+            Some(Action::CancelNoShow)
+        );
+        assert_eq!(next_action(&theirs, &clock(300), &me, false), None);
+    }
+
+    #[test]
+    fn finalizes_after_the_dispute_window() {
+        let me = Pubkey::new_unique();
+        let resolved = pool(MatchStatus::Resolved, Pubkey::new_unique());
+        assert_eq!(next_action(&resolved, &clock(399), &me, true), None);
+        assert_eq!(
+            next_action(&resolved, &clock(400), &me, true),
+            Some(Action::Finalize)
+        );
+        for status in [
+            MatchStatus::Settled,
+            MatchStatus::Disputed,
+            MatchStatus::CancelledDueToLowBets,
+        ] {
+            assert_eq!(
+                next_action(&pool(status, me), &clock(1_000), &me, true),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn retires_moves_once_the_match_leaves_resolution() {
+        let mut moves = MoveLog::default();
+        moves.pending.insert(1, (Move::Rock, Move::Paper));
+        moves.pending.insert(2, (Move::Scissors, Move::Rock));
+
+        moves.retire(1, MatchStatus::AwaitingResolution);
+        assert!(moves.pending.contains_key(&1));
+
+        moves.retire(1, MatchStatus::Resolved);
+        moves.retire(2, MatchStatus::CancelledResolverNoShow);
+        assert!(moves.pending.is_empty());
+        assert_eq!(moves.ai1_history, [Move::Rock]);
+        assert_eq!(moves.ai2_history, [Move::Paper]);
+
+        // Later polls of the same match do not record it twice.
+        moves.retire(1, MatchStatus::Settled);
+        assert_eq!(moves.ai1_history.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn retries_until_success() {
+        let policy = RetryPolicy {
+            attempts: 3,
+            initial_delay: Duration::from_millis(1),
+        };
+        let mut calls = 0;
+        let value = with_retries(policy, "test", || {
+            calls += 1;
+            let result = if calls < 3 {
+                Err(anyhow::anyhow!("transient"))
+            } else {
+                Ok(calls)
+            };
+            async move { result }
+        })
+        .await
+        .unwrap();
+        assert_eq!(value, 3);
+
+        let mut calls = 0;
+        let result: Result<()> = with_retries(policy, "test", || {
+            calls += 1;
+            async { Err(anyhow::anyhow!("permanent")) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+    }
+}
@@ -0,0 +1,115 @@
+// breakin/crates/breakin-keeper/src/main.rs
+//! Keeper service for the arena program. It polls every betting pool and sends whichever
+//! lifecycle instruction is due: opening scheduled betting, closing betting at the
+//! deadline, resolving the keeper's own matches with the configured AI strategies,
+//! finalizing after the dispute window and cancelling matches whose resolver timed out.
+//!
+//! The program has no commit/reveal step, so moves are chosen and revealed in one
+//! `resolve_match` call by the match authority, which must be the keeper's keypair.
+mod keeper;
+mod strategy;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use breakin_client::RpcClient;
+use clap::Parser;
+use log::{error, info};
+use solana_keypair::read_keypair_file;
+use solana_signer::Signer;
+
+use crate::keeper::{Keeper, RetryPolicy};
+use crate::strategy::StrategyKind;
+
+#[derive(Parser)]
+#[command(name = "breakin-keeper", version, about)]
+struct Args {
+    /// RPC URL. Defaults to a local validator.
+    #[arg(
+        long,
+        short = 'u',
+        env = "BREAKIN_RPC_URL",
+        default_value = "http://127.0.0.1:8899"
+    )]
+    url: String,
+    /// Keypair that pays for and signs keeper transactions. Defaults to ~/.config/solana/id.json.
+    #[arg(long, short = 'k', env = "BREAKIN_KEYPAIR")]
+    keypair: Option<PathBuf>,
+    /// Commitment used for reads and confirmations.
+    #[arg(long, default_value = "confirmed")]
+    commitment: String,
+    /// How often to poll the betting pools.
+    #[arg(long, default_value_t = 2_000)]
+    poll_interval_ms: u64,
+    /// Strategy that picks AI 1's moves.
+    #[arg(long, value_enum, default_value_t = StrategyKind::Random)]
+    ai1_strategy: StrategyKind,
+    /// Strategy that picks AI 2's moves.
+    #[arg(long, value_enum, default_value_t = StrategyKind::Random)]
+    ai2_strategy: StrategyKind,
+    /// Attempts per transaction before waiting for the next poll.
+    #[arg(long, default_value_t = 3)]
+    max_attempts: u32,
+    /// Delay before the first retry; doubled after each further failure.
+    #[arg(long, default_value_t = 500)]
+    retry_delay_ms: u64,
+    /// Leave matches of other resolvers alone even after they time out.
+    #[arg(long)]
+    no_cancel: bool,
+    /// Process the pools once and exit instead of polling.
+    #[arg(long)]
+    once: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let args = Args::parse();
+
+    let keypair_path = args
+        .keypair
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config/solana/id.json")))
+        .context("no keypair given and no home directory")?;
+    let signer = read_keypair_file(&keypair_path)
+        .map_err(|err| anyhow!("reading keypair {}: {err}", keypair_path.display()))?;
+    info!(
+        "Keeper {} on {} (AI 1: {:?}, AI 2: {:?})",
+        signer.pubkey(),
+        args.url,
+        args.ai1_strategy,
+        args.ai2_strategy
+    );
+
+    let mut keeper = Keeper::new(
+        RpcClient::new_with_commitment(args.url, args.commitment),
+        signer,
+        args.ai1_strategy.build(),
+        args.ai2_strategy.build(),
+        RetryPolicy {
+            attempts: args.max_attempts.max(1),
+            initial_delay: Duration::from_millis(args.retry_delay_ms),
+        },
+        !args.no_cancel,
+    );
+
+    if args.once {
+        return keeper.tick().await;
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_millis(args.poll_interval_ms));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                if let Err(err) = keeper.tick().await {
+                    error!("Poll failed: {err:#}");
+                }
+            }
+            _ = tokio::signal::ctrl_c() => {
+                info!("Shutting down");
+                return Ok(());
+            }
+        }
+    }
+}
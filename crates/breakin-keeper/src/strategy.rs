@@ -0,0 +1,134 @@
+// breakin/crates/breakin-keeper/src/strategy.rs
+//! Move selection for the two AIs. Each AI gets its own strategy instance and sees the
+//! moves its opponent played in earlier matches this keeper resolved.
+use breakin_client::program::Move;
+use clap::ValueEnum;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const MOVES: [Move; 3] = [Move::Rock, Move::Paper, Move::Scissors];
+
+pub trait Strategy: Send {
+    fn next_move(&mut self, match_id: u64, opponent_history: &[Move]) -> Move;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum StrategyKind {
+    /// Uniformly random moves.
+    Random,
+    /// Always rock.
+    Rock,
+    /// Always paper.
+    Paper,
+    /// Always scissors.
+    Scissors,
+    /// Rock, paper, scissors in turn by match id.
+    Cycle,
+    /// Plays what beats the opponent's previous move.
+    Counter,
+    /// Plays what beats the opponent's most frequent move.
+    Frequency,
+}
+
+impl StrategyKind {
+    pub fn build(self) -> Box<dyn Strategy> {
+        match self {
+            StrategyKind::Random => Box::new(RandomStrategy(StdRng::from_entropy())),
+            StrategyKind::Rock => Box::new(Fixed(Move::Rock)),
+            StrategyKind::Paper => Box::new(Fixed(Move::Paper)),
+            StrategyKind::Scissors => Box::new(Fixed(Move::Scissors)),
+            StrategyKind::Cycle => Box::new(Cycle),
+            StrategyKind::Counter => Box::new(Counter),
+            StrategyKind::Frequency => Box::new(Frequency),
+        }
+    }
+}
+
+/// The move that wins against `other`.
+pub fn beats(other: Move) -> Move {
+    match other {
+        Move::Rock => Move::Paper,
+        Move::Paper => Move::Scissors,
+        Move::Scissors => Move::Rock,
+    }
+}
+
+struct RandomStrategy(StdRng);
+
+impl Strategy for RandomStrategy {
+    fn next_move(&mut self, _match_id: u64, _opponent_history: &[Move]) -> Move {
+        MOVES[self.0.gen_range(0..MOVES.len())]
+    }
+}
+
+struct Fixed(Move);
+
+impl Strategy for Fixed {
+    fn next_move(&mut self, _match_id: u64, _opponent_history: &[Move]) -> Move {
+        self.0
+    }
+}
+
+struct Cycle;
+
+impl Strategy for Cycle {
+    fn next_move(&mut self, match_id: u64, _opponent_history: &[Move]) -> Move {
+        MOVES[(match_id % 3) as usize]
+    }
+}
+
+struct Counter;
+
+impl Strategy for Counter {
+    fn next_move(&mut self, match_id: u64, opponent_history: &[Move]) -> Move {
+        match opponent_history.last() {
+            Some(&last) => beats(last),
+            None => Cycle.next_move(match_id, opponent_history),
+        }
+    }
+}
+
+struct Frequency;
+
+impl Strategy for Frequency {
+    fn next_move(&mut self, match_id: u64, opponent_history: &[Move]) -> Move {
+        let count = |m: Move| opponent_history.iter().filter(|&&seen| seen == m).count();
+        // Ties go to the earliest move in MOVES, so the choice is deterministic.
+        match MOVES.into_iter().rev().max_by_key(|&m| count(m)) {
+            Some(favourite) if count(favourite) > 0 => beats(favourite),
+            _ => Cycle.next_move(match_id, opponent_history),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use breakin_client::program::{determine_rps_winner, Winner};
+
+    #[test]
+    fn beats_wins_every_time() {
+        for m in MOVES {
+            assert_eq!(determine_rps_winner(beats(m), m), Winner::Ai1);
+        }
+    }
+
+    #[test]
+    fn counter_beats_the_last_move() {
+        let mut counter = StrategyKind::Counter.build();
+        assert_eq!(
+            counter.next_move(0, &[Move::Rock, Move::Scissors]),
+            Move::Rock
+        );
+        assert_eq!(counter.next_move(1, &[]), Move::Paper);
+    }
+
+    #[test]
+    fn frequency_beats_the_favourite() {
+        let mut frequency = StrategyKind::Frequency.build();
+        let history = [Move::Paper, Move::Rock, Move::Paper];
+        assert_eq!(frequency.next_move(0, &history), Move::Scissors);
+        // Tie between rock and paper: rock comes first.
+        assert_eq!(frequency.next_move(0, &history[..2]), Move::Paper);
+    }
+}
@@ -0,0 +1,120 @@
+// breakin/crates/breakin-keeper/tests/validator.rs
+//! End-to-end run of a match driven by the keeper against a local validator. Ignored by
+//! default; start a fresh validator with the program deployed, then run
+//!
+//! ```text
+//! solana-test-validator --reset --bpf-program <program id> target/deploy/breakin.so
+//! cargo test -p breakin-keeper --test validator -- --ignored
+//! ```
+//!
+//! The keeper and the bettor share the keypair from `BREAKIN_KEYPAIR` or the Solana CLI
+//! default, which the validator's faucet funds.
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+use breakin_client::anchor_lang::solana_program::instruction::Instruction;
+use breakin_client::instructions::{self, CreateMatchArgs};
+use breakin_client::program::{BettingTime, GameConfig, MatchStatus, Prediction};
+use breakin_client::{fetch, RpcClient};
+use solana_keypair::{read_keypair_file, Keypair};
+use solana_signer::Signer;
+
+const RPC_URL: &str = "http://127.0.0.1:8899";
+
+fn keypair_path() -> PathBuf {
+    std::env::var_os("BREAKIN_KEYPAIR")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config/solana/id.json")))
+        .expect("no keypair configured")
+}
+
+/// One pass of the keeper over every pool.
+fn keeper_tick() {
+    let status = Command::new(env!("CARGO_BIN_EXE_breakin-keeper"))
+        .args(["--url", RPC_URL, "--once", "--keypair"])
+        .arg(keypair_path())
+        .status()
+        .expect("running breakin-keeper");
+    assert!(status.success(), "breakin-keeper --once failed");
+}
+
+async fn send(rpc: &RpcClient, signer: &Keypair, instruction: Instruction) {
+    rpc.send_and_confirm(&[instruction], signer, &[])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs a local solana-test-validator with the program deployed"]
+async fn keeper_drives_a_match_to_settlement() {
+    let rpc = RpcClient::new_with_commitment(RPC_URL, "confirmed");
+    let signer: Keypair = read_keypair_file(keypair_path()).expect("reading keypair");
+    let me = signer.pubkey();
+
+    if fetch::game_state(&rpc).await.is_err() {
+        let config = GameConfig {
+            default_betting_duration_slots: 20,
+            min_betting_duration_slots: 10,
+            max_betting_duration_slots: 0,
+            min_bet_threshold_floor: 0,
+            min_bet_threshold_ceiling: 0,
+            platform_fee_bps: 0,
+            resolution_timeout_slots: 1_500,
+            dispute_window_slots: 5,
+            dispute_bond_lamports: 1_000_000,
+            resolver_bond_lamports: 1_000_000,
+            claim_window_slots: 1_512_000,
+        };
+        send(&rpc, &signer, instructions::initialize_game(&me, config)).await;
+    }
+    let match_id = fetch::game_state(&rpc).await.unwrap().next_match_id;
+    let deadline = rpc.get_slot().await.unwrap() + 20;
+    send(
+        &rpc,
+        &signer,
+        instructions::create_match(
+            &me,
+            match_id,
+            CreateMatchArgs {
+                betting_deadline: Some(BettingTime::Slot(deadline)),
+                ..Default::default()
+            },
+        ),
+    )
+    .await;
+    let pool = fetch::betting_pool(&rpc, match_id).await.unwrap().unwrap();
+    send(
+        &rpc,
+        &signer,
+        instructions::place_bet(&me, &pool, 1_000_000, Prediction::Ai1),
+    )
+    .await;
+
+    // Closing betting, resolving and finalizing each take a pass once they are due.
+    let mut status = pool.status;
+    for _ in 0..300 {
+        keeper_tick();
+        status = fetch::betting_pool(&rpc, match_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .status;
+        if status == MatchStatus::Settled {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(400)).await;
+    }
+    assert_eq!(status, MatchStatus::Settled);
+
+    let pool = fetch::betting_pool(&rpc, match_id).await.unwrap().unwrap();
+    let bet = fetch::user_bet(&rpc, &me, match_id).await.unwrap().unwrap();
+    send(
+        &rpc,
+        &signer,
+        instructions::claim_winnings(&me, &pool, &bet),
+    )
+    .await;
+    let bet = fetch::user_bet(&rpc, &me, match_id).await.unwrap().unwrap();
+    assert!(bet.claimed);
+}
@@ -0,0 +1,385 @@
+// breakin/programs/breakin/src/legacy.rs
+//! Account layouts from before versioning (version 0), kept only so the `migrate_*`
+//! instructions can read old accounts and rewrite them in the current layout.
+//!
+//! These are plain Borsh structs rather than `#[account]`s: the data after the
+//! discriminator is decoded by hand once the owner and discriminator have been checked.
+use anchor_lang::prelude::*;
+
+use crate::settlement::{settle, Bet, Outcome, PoolTotals};
+use crate::{
+    BettingPool, BettingTime, ClaimAmounts, GameConfig, GameState, MatchRecord, MatchStatus, Move,
+    Prediction, RpsError, UserBet, Winner, ACCOUNT_VERSION,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct GameStateV0 {
+    pub authority: Pubkey,
+    pub next_match_id: u64,
+    pub total_matches: u64,
+    pub bump: u8,
+}
+impl GameStateV0 {
+    pub const LEN: usize = 32 + 8 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct BettingPoolV0 {
+    pub authority: Pubkey,
+    pub match_id: u64,
+    pub total_ai1_bets: u64,
+    pub total_ai2_bets: u64,
+    pub status: MatchStatus, // only the first four variants existed
+    pub bump: u8,
+    pub pool_authority_bump: u8,
+    pub betting_deadline_slot: u64,
+    pub min_bet_threshold: u64,
+}
+impl BettingPoolV0 {
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 1 + 1 + 1 + 8 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct MatchRecordV0 {
+    pub match_id: u64,
+    pub timestamp: i64,
+    pub ai1_move: Move,
+    pub ai2_move: Move,
+    pub winner: Winner,
+    pub total_bet_amount: u64,
+    pub bump: u8,
+}
+impl MatchRecordV0 {
+    pub const LEN: usize = 8 + 8 + 1 + 1 + 1 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UserBetV0 {
+    pub better: Pubkey,
+    pub match_id: u64,
+    pub prediction: Prediction,
+    pub amount: u64,
+    pub claimed: bool,
+    pub bump: u8,
+}
+impl UserBetV0 {
+    pub const LEN: usize = 32 + 8 + 1 + 8 + 1 + 1;
+}
+
+impl GameStateV0 {
+    /// Current layout. The configuration did not exist yet and is supplied by the authority.
+    pub fn migrate(self, config: &GameConfig) -> GameState {
+        let mut game_state = GameState {
+            version: ACCOUNT_VERSION,
+            authority: self.authority,
+            next_match_id: self.next_match_id,
+            total_matches: self.total_matches,
+            bump: self.bump,
+            ..Default::default()
+        };
+        game_state.apply_config(config);
+        game_state
+    }
+}
+
+impl BettingPoolV0 {
+    /// Current layout: an unlimited parimutuel pool with no platform fee. Timers that
+    /// did not exist yet start at `slot`. `claimed` are the bets already paid under
+    /// version 0; they are booked again so the last claimant still takes the remainder.
+    /// Settled pools need the match `winner` to book them.
+    pub fn migrate(
+        self,
+        game_state: &GameState,
+        slot: u64,
+        winner: Option<Winner>,
+        claimed: &[Bet],
+    ) -> Result<BettingPool> {
+        let mut betting_pool = BettingPool {
+            version: ACCOUNT_VERSION,
+            authority: self.authority,
+            match_id: self.match_id,
+            total_ai1_bets: self.total_ai1_bets,
+            total_ai2_bets: self.total_ai2_bets,
+            status: self.status,
+            bump: self.bump,
+            pool_authority_bump: self.pool_authority_bump,
+            betting_deadline: BettingTime::Slot(self.betting_deadline_slot),
+            min_bet_threshold: self.min_bet_threshold,
+            resolution_timeout_slots: game_state.resolution_timeout_slots,
+            claim_window_slots: game_state.claim_window_slots,
+            ..Default::default()
+        };
+        match betting_pool.status {
+            MatchStatus::AwaitingResolution => {
+                betting_pool.resolution_deadline_slot = slot
+                    .checked_add(betting_pool.resolution_timeout_slots)
+                    .ok_or(RpsError::Overflow)?;
+            }
+            // Version 0 settled pools were claimable straight away, with no dispute window.
+            MatchStatus::Settled | MatchStatus::CancelledDueToLowBets => {
+                betting_pool.open_claim_window(slot)?;
+            }
+            _ => {}
+        }
+
+        if !claimed.is_empty() {
+            let outcome = match betting_pool.status {
+                MatchStatus::Settled => {
+                    Outcome::Settled(winner.ok_or(RpsError::MatchRecordRequired)?)
+                }
+                MatchStatus::CancelledDueToLowBets => Outcome::Refunded,
+                _ => return err!(RpsError::MatchNotReadyForClaimOrRefund),
+            };
+            let totals = PoolTotals {
+                ai1: betting_pool.total_ai1_bets,
+                ai2: betting_pool.total_ai2_bets,
+            };
+            for bet in claimed {
+                // Version 0 took no fee and had no resolver bond.
+                let settlement = settle(totals, outcome, *bet, 0)?;
+                betting_pool.record_claim(&ClaimAmounts {
+                    payout: settlement.payout,
+                    stake: settlement.stake,
+                    ..Default::default()
+                })?;
+            }
+        }
+        Ok(betting_pool)
+    }
+
+    /// Lamports version 0 claims have taken out of a pool holding `lamports`. Claims
+    /// were paid straight from the pool, which otherwise only held its rent and the bets.
+    pub fn paid_out(&self, lamports: u64, rent: &Rent) -> Result<u64> {
+        let funded = rent
+            .minimum_balance(8 + Self::LEN)
+            .checked_add(self.total_ai1_bets)
+            .and_then(|total| total.checked_add(self.total_ai2_bets))
+            .ok_or(RpsError::Overflow)?;
+        Ok(funded.saturating_sub(lamports))
+    }
+}
+
+impl MatchRecordV0 {
+    pub fn migrate(self) -> MatchRecord {
+        MatchRecord {
+            version: ACCOUNT_VERSION,
+            match_id: self.match_id,
+            timestamp: self.timestamp,
+            ai1_move: self.ai1_move,
+            ai2_move: self.ai2_move,
+            winner: self.winner,
+            total_bet_amount: self.total_bet_amount,
+            bump: self.bump,
+            ..Default::default()
+        }
+    }
+}
+
+impl UserBetV0 {
+    pub fn migrate(self) -> UserBet {
+        UserBet {
+            version: ACCOUNT_VERSION,
+            better: self.better,
+            match_id: self.match_id,
+            prediction: self.prediction,
+            amount: self.amount,
+            claimed: self.claimed,
+            bump: self.bump,
+            origin: self.better,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute_claim;
+
+    const SLOT: u64 = 10_000;
+
+    fn game_state() -> GameState {
+        GameState {
+            resolution_timeout_slots: 1_500,
+            claim_window_slots: 1_512_000,
+            ..Default::default()
+        }
+    }
+
+    fn pool(status: MatchStatus) -> BettingPoolV0 {
+        BettingPoolV0 {
+            authority: Pubkey::new_unique(),
+            match_id: 7,
+            total_ai1_bets: 300,
+            total_ai2_bets: 100,
+            status,
+            bump: 254,
+            pool_authority_bump: 253,
+            betting_deadline_slot: 9_000,
+            min_bet_threshold: 50,
+        }
+    }
+
+    fn bet(prediction: Prediction, amount: u64) -> Bet {
+        Bet { prediction, amount }
+    }
+
+    #[test]
+    fn game_state_takes_the_supplied_config() {
+        let config = GameConfig {
+            platform_fee_bps: 200,
+            claim_window_slots: 1_000,
+            ..Default::default()
+        };
+        let authority = Pubkey::new_unique();
+        let game_state = GameStateV0 {
+            authority,
+            next_match_id: 5,
+            total_matches: 4,
+            bump: 255,
+        }
+        .migrate(&config);
+        assert_eq!(game_state.version, ACCOUNT_VERSION);
+        assert_eq!(game_state.authority, authority);
+        assert_eq!(game_state.next_match_id, 5);
+        assert_eq!(game_state.total_matches, 4);
+        assert_eq!(game_state.bump, 255);
+        assert_eq!(game_state.config(), config);
+    }
+
+    #[test]
+    fn open_and_awaiting_pools_keep_their_deadlines() {
+        let open = pool(MatchStatus::OpenForBetting)
+            .migrate(&game_state(), SLOT, None, &[])
+            .unwrap();
+        assert_eq!(open.version, ACCOUNT_VERSION);
+        assert_eq!(open.status, MatchStatus::OpenForBetting);
+        assert_eq!(open.betting_deadline, BettingTime::Slot(9_000));
+        assert_eq!(open.min_bet_threshold, 50);
+        assert_eq!((open.bump, open.pool_authority_bump), (254, 253));
+        assert_eq!(open.resolution_deadline_slot, 0);
+        assert_eq!(open.claim_deadline_slot, 0);
+
+        let awaiting = pool(MatchStatus::AwaitingResolution)
+            .migrate(&game_state(), SLOT, None, &[])
+            .unwrap();
+        assert_eq!(awaiting.resolution_deadline_slot, SLOT + 1_500);
+        assert_eq!(awaiting.claim_deadline_slot, 0);
+    }
+
+    #[test]
+    fn finished_pools_open_their_claim_window() {
+        for status in [MatchStatus::Settled, MatchStatus::CancelledDueToLowBets] {
+            let betting_pool = pool(status)
+                .migrate(&game_state(), SLOT, Some(Winner::Ai1), &[])
+                .unwrap();
+            assert_eq!(betting_pool.status, status);
+            assert_eq!(betting_pool.claim_deadline_slot, SLOT + 1_512_000);
+            assert_eq!(betting_pool.fee_bps, 0);
+            assert_eq!(betting_pool.total_paid_out, 0);
+            assert_eq!(betting_pool.claimed_count, 0);
+        }
+    }
+
+    #[test]
+    fn claimed_bets_carry_over_so_the_last_winner_takes_the_dust() {
+        // Ai1 won. One of two winning bets and the only losing bet were claimed under
+        // version 0.
+        let claimed = [bet(Prediction::Ai1, 100), bet(Prediction::Ai2, 100)];
+        let betting_pool = pool(MatchStatus::Settled)
+            .migrate(&game_state(), SLOT, Some(Winner::Ai1), &claimed)
+            .unwrap();
+        assert_eq!(betting_pool.total_paid_out, 133);
+        assert_eq!(betting_pool.claimed_stake, 100);
+        assert_eq!(betting_pool.claimed_count, 2);
+
+        // The remaining winner is owed 66 profit pro rata, plus the lamport of dust.
+        let match_record = MatchRecord {
+            match_id: 7,
+            winner: Winner::Ai1,
+            ..Default::default()
+        };
+        let claim =
+            compute_claim(&betting_pool, Some(&match_record), Prediction::Ai1, 200, 0).unwrap();
+        assert_eq!(claim.payout, 267);
+        assert_eq!(betting_pool.total_paid_out + claim.payout, 400);
+    }
+
+    #[test]
+    fn claimed_refunds_carry_over() {
+        let betting_pool = pool(MatchStatus::CancelledDueToLowBets)
+            .migrate(&game_state(), SLOT, None, &[bet(Prediction::Ai2, 100)])
+            .unwrap();
+        assert_eq!(betting_pool.total_paid_out, 100);
+        assert_eq!(betting_pool.claimed_stake, 100);
+        assert_eq!(betting_pool.claimed_count, 1);
+    }
+
+    #[test]
+    fn settled_claims_need_the_winner() {
+        let result = pool(MatchStatus::Settled).migrate(
+            &game_state(),
+            SLOT,
+            None,
+            &[bet(Prediction::Ai1, 100)],
+        );
+        assert_eq!(result.err(), Some(RpsError::MatchRecordRequired.into()));
+    }
+
+    #[test]
+    fn paid_out_is_read_from_the_pool_balance() {
+        let rent = Rent::default();
+        let pool = pool(MatchStatus::Settled);
+        let funded = rent.minimum_balance(8 + BettingPoolV0::LEN) + 400;
+        assert_eq!(pool.paid_out(funded, &rent).unwrap(), 0);
+        assert_eq!(pool.paid_out(funded - 133, &rent).unwrap(), 133);
+        // Lamports sent to the pool on top of that only hide claims, never invent them.
+        assert_eq!(pool.paid_out(funded + 1_000, &rent).unwrap(), 0);
+    }
+
+    #[test]
+    fn match_record_keeps_its_result() {
+        let match_record = MatchRecordV0 {
+            match_id: 7,
+            timestamp: 1_700_000_000,
+            ai1_move: Move::Rock,
+            ai2_move: Move::Scissors,
+            winner: Winner::Ai1,
+            total_bet_amount: 400,
+            bump: 252,
+        }
+        .migrate();
+        assert_eq!(match_record.version, ACCOUNT_VERSION);
+        assert_eq!(match_record.match_id, 7);
+        assert_eq!(match_record.timestamp, 1_700_000_000);
+        assert_eq!(match_record.ai1_move, Move::Rock);
+        assert_eq!(match_record.ai2_move, Move::Scissors);
+        assert_eq!(match_record.winner, Winner::Ai1);
+        assert_eq!(match_record.total_bet_amount, 400);
+        assert_eq!(match_record.bump, 252);
+        assert!(!match_record.overturned);
+    }
+
+    #[test]
+    fn user_bet_originates_with_its_better() {
+        let better = Pubkey::new_unique();
+        let user_bet = UserBetV0 {
+            better,
+            match_id: 7,
+            prediction: Prediction::Ai2,
+            amount: 100,
+            claimed: true,
+            bump: 251,
+        }
+        .migrate();
+        assert_eq!(user_bet.version, ACCOUNT_VERSION);
+        assert_eq!(user_bet.better, better);
+        assert_eq!(user_bet.origin, better);
+        assert_eq!(user_bet.match_id, 7);
+        assert_eq!(user_bet.prediction, Prediction::Ai2);
+        assert_eq!(user_bet.amount, 100);
+        assert!(user_bet.claimed);
+        assert_eq!(user_bet.bump, 251);
+        assert_eq!(user_bet.tokenized_amount, 0);
+    }
+}
@@ -1,25 +1,83 @@
 // breakin/programs/breakin/src/lib.rs
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::clock::Clock;
-use std::mem::size_of;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+pub mod legacy;
+pub mod payout;
+pub mod settlement;
+use legacy::{BettingPoolV0, GameStateV0, MatchRecordV0, UserBetV0};
+pub use payout::{compute_payout, fixed_odds_payout};
+use settlement::{predicted, settle, Bet, Outcome, PoolTotals, Settlement};
 
 // IMPORTANT NOTE TO SELF: Replace with your actual Program ID after first deployment!
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
+pub const BPS_DENOMINATOR: u64 = 10_000;
+/// Upper bound for the platform fee taken from parimutuel winnings (10%).
+pub const MAX_PLATFORM_FEE_BPS: u64 = 1_000;
+/// LP shares use the same precision as lamports.
+pub const LP_MINT_DECIMALS: u8 = 9;
+/// Shares minted into the LP escrow on the first deposit and never redeemable. They keep
+/// the share supply from returning to a handful of shares that a donation to the vault
+/// could inflate.
+pub const LOCKED_LP_SHARES: u64 = 1_000_000;
+/// Smallest deposit into a vault with no shares outstanding (0.01 SOL).
+pub const MIN_FIRST_DEPOSIT_LAMPORTS: u64 = 10_000_000;
+/// One position token base unit represents one lamport staked.
+pub const POSITION_MINT_DECIMALS: u8 = 9;
+/// Layout version written to GameState, BettingPool, MatchRecord and UserBet. Accounts
+/// created before versioning have no version byte and count as version 0.
+pub const ACCOUNT_VERSION: u8 = 1;
+/// Rough slot length used to compare timestamp deadlines with slot-based bounds.
+pub const APPROX_SLOT_DURATION_MS: u64 = 400;
+/// Paid to whoever runs the `distribute` crank for each bet it pays out, out of the
+/// pool's uncollected fees.
+pub const KEEPER_REWARD_LAMPORTS: u64 = 5_000;
+
 #[program]
 pub mod solana_rps_arena {
     use super::*;
 
-    pub fn initialize_game(ctx: Context<InitializeGame>) -> Result<()> {
+    pub fn initialize_game(ctx: Context<InitializeGame>, config: GameConfig) -> Result<()> {
+        config.validate()?;
         let game_state = &mut ctx.accounts.game_state;
         game_state.authority = ctx.accounts.authority.key();
         game_state.next_match_id = 1;
         game_state.total_matches = 0;
+        game_state.version = ACCOUNT_VERSION;
         game_state.bump = ctx.bumps.game_state;
+        game_state.apply_config(&config);
+
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.total_collected = 0;
+        treasury.bump = ctx.bumps.treasury;
+
+        msg!(
+            "RPS Arena GameState initialized by: {}. Next Match ID: {}. Dispute window: {} slots, bond: {} lamports. Resolver bond: {} lamports, timeout: {} slots. Claim window: {} slots. Treasury PDA: {}",
+            game_state.authority,
+            game_state.next_match_id,
+            game_state.dispute_window_slots,
+            game_state.dispute_bond_lamports,
+            game_state.resolver_bond_lamports,
+            game_state.resolution_timeout_slots,
+            game_state.claim_window_slots,
+            treasury.key()
+        );
+        Ok(())
+    }
+
+    /// Replaces the game-wide configuration. Matches already created keep the values
+    /// they snapshotted at creation.
+    pub fn update_config(ctx: Context<UpdateConfig>, config: GameConfig) -> Result<()> {
+        config.validate()?;
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.apply_config(&config);
         msg!(
-            "RPS Arena GameState initialized by: {}. Next Match ID: {}",
+            "GameState config updated by {}: {:?}",
             game_state.authority,
-            game_state.next_match_id
+            config
         );
         Ok(())
     }
@@ -27,24 +85,118 @@ pub mod solana_rps_arena {
     pub fn create_match(
         ctx: Context<CreateMatch>,
         min_bet_threshold_lamports: u64,
-        betting_duration_slots: u64,
+        betting_deadline: Option<BettingTime>, // None uses the configured default duration
+        betting_opens_at: Option<BettingTime>,
+        bet_limits: BetLimits,
+        imbalance_guard: Option<ImbalanceGuard>,
+        market: MarketKind,
     ) -> Result<()> {
+        bet_limits.validate()?;
+        market.validate()?;
+        // Fixed odds are backed by the house vault, so only the game authority may post them.
+        if market.is_fixed_odds() {
+            require!(
+                ctx.accounts.match_creator_signer.key() == ctx.accounts.game_state.authority,
+                RpsError::Unauthorized
+            );
+        }
+        if let Some(guard) = imbalance_guard {
+            guard.validate()?;
+        }
+        let config = ctx.accounts.game_state.config();
+        require!(
+            min_bet_threshold_lamports >= config.min_bet_threshold_floor
+                && (config.min_bet_threshold_ceiling == 0
+                    || min_bet_threshold_lamports <= config.min_bet_threshold_ceiling),
+            RpsError::MinBetThresholdOutOfBounds
+        );
+
+        let clock = Clock::get()?;
+        let (status, betting_opens_at) = match betting_opens_at {
+            Some(opens_at) if !opens_at.has_passed(&clock) => (MatchStatus::Scheduled, opens_at),
+            _ => (MatchStatus::OpenForBetting, BettingTime::Slot(clock.slot)),
+        };
+        let opens_at_slot = betting_opens_at.approx_slot(&clock);
+        let betting_deadline = match betting_deadline {
+            Some(deadline) => deadline,
+            None => {
+                require!(
+                    config.default_betting_duration_slots > 0,
+                    RpsError::NoDefaultBettingDuration
+                );
+                BettingTime::Slot(
+                    opens_at_slot
+                        .checked_add(config.default_betting_duration_slots)
+                        .ok_or(RpsError::Overflow)?,
+                )
+            }
+        };
+        require!(
+            !betting_deadline.has_passed(&clock),
+            RpsError::InvalidBettingDeadline
+        );
+        if status == MatchStatus::Scheduled {
+            require!(
+                !betting_deadline.is_before(&betting_opens_at, &clock),
+                RpsError::InvalidBettingWindow
+            );
+        }
+        // Timestamp deadlines are converted at APPROX_SLOT_DURATION_MS, so the bounds are
+        // only approximate for them.
+        let betting_duration_slots = betting_deadline
+            .approx_slot(&clock)
+            .saturating_sub(opens_at_slot);
+        require!(
+            betting_duration_slots >= config.min_betting_duration_slots
+                && (config.max_betting_duration_slots == 0
+                    || betting_duration_slots <= config.max_betting_duration_slots),
+            RpsError::BettingDurationOutOfBounds
+        );
+
+        // Escrow the resolver bond before taking mutable borrows for the state updates.
+        let bond = ctx.accounts.game_state.resolver_bond_lamports;
+        if bond > 0 {
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.match_creator_signer.to_account_info(),
+                    to: ctx.accounts.resolver_bond.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, bond)?;
+        }
+
         let game_state = &mut ctx.accounts.game_state; // mutable borrow
         let betting_pool = &mut ctx.accounts.betting_pool; // mutable borrow
-        let clock = Clock::get()?;
 
         betting_pool.match_id = game_state.next_match_id;
         betting_pool.total_ai1_bets = 0;
         betting_pool.total_ai2_bets = 0;
-        betting_pool.status = MatchStatus::OpenForBetting;
+        betting_pool.status = status;
+        betting_pool.betting_opens_at = betting_opens_at;
         betting_pool.authority = ctx.accounts.match_creator_signer.key();
         betting_pool.min_bet_threshold = min_bet_threshold_lamports;
-        betting_pool.betting_deadline_slot = clock
-            .slot
-            .checked_add(betting_duration_slots)
-            .ok_or(RpsError::Overflow)?;
+        betting_pool.bet_limits = bet_limits;
+        betting_pool.imbalance_guard = imbalance_guard;
+        betting_pool.market = market;
+        // Fixed-odds markets earn the house its margin through the odds instead.
+        betting_pool.fee_bps = if market.is_fixed_odds() {
+            0
+        } else {
+            game_state.platform_fee_bps
+        };
+        betting_pool.betting_deadline = betting_deadline;
+        betting_pool.version = ACCOUNT_VERSION;
         betting_pool.bump = ctx.bumps.betting_pool;
         betting_pool.pool_authority_bump = ctx.bumps.betting_pool_authority;
+        betting_pool.resolution_timeout_slots = game_state.resolution_timeout_slots;
+        betting_pool.claim_window_slots = game_state.claim_window_slots;
+
+        let resolver_bond = &mut ctx.accounts.resolver_bond;
+        resolver_bond.match_id = betting_pool.match_id;
+        resolver_bond.resolver = betting_pool.authority;
+        resolver_bond.amount = bond;
+        resolver_bond.bump = ctx.bumps.resolver_bond;
 
         game_state.next_match_id = game_state // game_state still mutably borrowed
             .next_match_id
@@ -52,33 +204,114 @@ pub mod solana_rps_arena {
             .ok_or(RpsError::Overflow)?;
 
         msg!(
-            "Match #{} created by {}. Betting open until slot ~{}. Min threshold: {} lamports. Pool PDA: {}",
+            "Match #{} created by {}. Market: {:?}. Status: {:?}. Betting opens {:?}, closes {:?}. Min threshold: {} lamports. Resolver bond: {} lamports. Pool PDA: {}",
             betting_pool.match_id,
             betting_pool.authority,
-            betting_pool.betting_deadline_slot,
+            betting_pool.market,
+            betting_pool.status,
+            betting_pool.betting_opens_at,
+            betting_pool.betting_deadline,
             betting_pool.min_bet_threshold,
+            resolver_bond.amount,
             betting_pool.key()
         );
         Ok(())
     } // mutable borrows of game_state and betting_pool end here
 
-    pub fn place_bet(ctx: Context<PlaceBet>, amount: u64, prediction_raw: u8) -> Result<()> {
-        // Perform read-only checks on betting_pool first
+    pub fn open_betting(ctx: Context<OpenBetting>) -> Result<()> {
+        let betting_pool = &mut ctx.accounts.betting_pool;
+        let clock = Clock::get()?;
+
         require!(
-            ctx.accounts.betting_pool.status == MatchStatus::OpenForBetting,
-            RpsError::BettingClosedOrNotOpen
+            betting_pool.status == MatchStatus::Scheduled,
+            RpsError::MatchNotScheduled
         );
+        require!(
+            betting_pool.betting_opens_at.has_passed(&clock),
+            RpsError::BettingNotYetOpen
+        );
+
+        betting_pool.status = MatchStatus::OpenForBetting;
+        msg!(
+            "Match #{} betting opened (Slot {}, time {}). Closes {:?}.",
+            betting_pool.match_id,
+            clock.slot,
+            clock.unix_timestamp,
+            betting_pool.betting_deadline
+        );
+        Ok(())
+    }
+
+    /// Entrypoint for clients that still send the prediction as a raw byte (0 = AI1,
+    /// 1 = AI2). New clients should call place_bet.
+    pub fn place_bet_legacy(ctx: Context<PlaceBet>, amount: u64, prediction_raw: u8) -> Result<()> {
+        place_bet(ctx, amount, Prediction::from_u8(prediction_raw)?)
+    }
+
+    pub fn place_bet(ctx: Context<PlaceBet>, amount: u64, prediction: Prediction) -> Result<()> {
+        // Perform read-only checks on betting_pool first
         let clock = Clock::get()?;
+        match ctx.accounts.betting_pool.status {
+            MatchStatus::OpenForBetting => {}
+            // A scheduled match opens with its first bet once the open time has passed,
+            // so open_betting is not required before betting starts.
+            MatchStatus::Scheduled => require!(
+                ctx.accounts
+                    .betting_pool
+                    .betting_opens_at
+                    .has_passed(&clock),
+                RpsError::BettingNotYetOpen
+            ),
+            _ => return err!(RpsError::BettingClosedOrNotOpen),
+        }
         require!(
-            clock.slot < ctx.accounts.betting_pool.betting_deadline_slot,
+            !ctx.accounts
+                .betting_pool
+                .betting_deadline
+                .has_passed(&clock),
             RpsError::BettingDeadlinePassed
         );
         require!(amount > 0, RpsError::BetAmountZero);
 
-        let prediction = match prediction_raw {
-            0 => Prediction::Ai1,
-            1 => Prediction::Ai2,
-            _ => return err!(RpsError::InvalidPrediction),
+        let tokenized = ctx.accounts.position_mint.is_some();
+
+        // Repeat bets top up the same UserBet, so they must back the same side.
+        let previous_amount = ctx.accounts.user_bet.amount;
+        let previous_total = previous_amount
+            .checked_add(ctx.accounts.user_bet.tokenized_amount)
+            .ok_or(RpsError::Overflow)?;
+        if previous_total > 0 {
+            require!(
+                ctx.accounts.user_bet.prediction == prediction,
+                RpsError::PredictionMismatch
+            );
+            // A sold bet keeps its original PDA, so its seller cannot top it up any more.
+            require!(
+                ctx.accounts.user_bet.better == ctx.accounts.better.key(),
+                RpsError::UserBetOwnerMismatch
+            );
+        }
+
+        let pool_total = ctx
+            .accounts
+            .betting_pool
+            .total_ai1_bets
+            .checked_add(ctx.accounts.betting_pool.total_ai2_bets)
+            .ok_or(RpsError::Overflow)?;
+        ctx.accounts
+            .betting_pool
+            .bet_limits
+            .check(amount, previous_total, pool_total)?;
+
+        // Fixed-odds stakes go straight to the house vault, which pays those bets out.
+        let stake_destination = if ctx.accounts.betting_pool.market.is_fixed_odds() {
+            ctx.accounts
+                .house_vault
+                .as_ref()
+                .ok_or(RpsError::HouseVaultRequired)?
+                .to_account_info()
+        } else {
+            ctx.accounts.betting_pool.to_account_info()
         };
 
         // CPI Transfer: uses immutable borrows of AccountInfo
@@ -86,13 +319,60 @@ pub mod solana_rps_arena {
             ctx.accounts.system_program.to_account_info(),
             anchor_lang::system_program::Transfer {
                 from: ctx.accounts.better.to_account_info(),
-                to: ctx.accounts.betting_pool.to_account_info(), // Immutable borrow for CPI
+                to: stake_destination, // Immutable borrow for CPI
             },
         );
         anchor_lang::system_program::transfer(cpi_context, amount)?;
 
+        // Tokenized stakes are represented by position tokens instead of the UserBet,
+        // so whoever holds the tokens can redeem them.
+        if tokenized {
+            require!(
+                ctx.accounts.betting_pool.position_tokens_enabled,
+                RpsError::PositionTokensNotEnabled
+            );
+            let (
+                Some(position_mint),
+                Some(position_token_account),
+                Some(betting_pool_authority),
+                Some(token_program),
+            ) = (
+                ctx.accounts.position_mint.as_ref(),
+                ctx.accounts.position_token_account.as_ref(),
+                ctx.accounts.betting_pool_authority.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            )
+            else {
+                return err!(RpsError::PositionAccountsRequired);
+            };
+            require!(
+                position_token_account.mint == position_mint.key(),
+                RpsError::PositionMintMismatch
+            );
+
+            let authority_seeds = &[
+                b"betting_pool_authority".as_ref(),
+                &ctx.accounts.betting_pool.match_id.to_le_bytes(),
+                &[ctx.accounts.betting_pool.pool_authority_bump],
+            ];
+            let signer_seeds = &[&authority_seeds[..]];
+            token::mint_to(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    token::MintTo {
+                        mint: position_mint.to_account_info(),
+                        to: position_token_account.to_account_info(),
+                        authority: betting_pool_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?;
+        }
+
         // Now, obtain the mutable borrow for betting_pool updates AFTER the CPI
         let betting_pool = &mut ctx.accounts.betting_pool;
+        betting_pool.status = MatchStatus::OpenForBetting;
         match prediction {
             Prediction::Ai1 => {
                 betting_pool.total_ai1_bets = betting_pool
@@ -107,24 +387,58 @@ pub mod solana_rps_arena {
                     .ok_or(RpsError::Overflow)?;
             }
         }
+
+        let locked_odds_bps = betting_pool.market.odds_for(prediction);
+        if let Some(odds_bps) = locked_odds_bps {
+            betting_pool.add_house_liability(prediction, previous_amount, amount, odds_bps)?;
+            let house_vault = ctx
+                .accounts
+                .house_vault
+                .as_mut()
+                .ok_or(RpsError::HouseVaultRequired)?;
+            let worst_case = betting_pool.worst_case_house_liability()?;
+            set_house_reservation(betting_pool, house_vault, worst_case)?;
+
+            let vault_info = house_vault.to_account_info();
+            let rent_exempt_minimum = Rent::get()?.minimum_balance(vault_info.data_len());
+            require!(
+                vault_info.lamports().saturating_sub(rent_exempt_minimum)
+                    >= house_vault.total_liability,
+                RpsError::HouseCannotCoverLiability
+            );
+        }
         // betting_pool mutable borrow scope can end here if not needed for user_bet.match_id
 
         let user_bet = &mut ctx.accounts.user_bet;
         user_bet.better = ctx.accounts.better.key();
+        user_bet.origin = ctx.accounts.better.key();
         user_bet.match_id = betting_pool.match_id;
         user_bet.prediction = prediction;
-        user_bet.amount = amount;
+        if tokenized {
+            user_bet.tokenized_amount = user_bet
+                .tokenized_amount
+                .checked_add(amount)
+                .ok_or(RpsError::Overflow)?;
+        } else {
+            user_bet.amount = previous_amount
+                .checked_add(amount)
+                .ok_or(RpsError::Overflow)?;
+        }
         user_bet.claimed = false;
+        user_bet.version = ACCOUNT_VERSION;
         user_bet.bump = ctx.bumps.user_bet;
+        user_bet.locked_odds_bps = locked_odds_bps.unwrap_or(0);
 
         msg!(
-            "User {} bet {} on {:?} for Match #{}. Current slot: {}, Deadline: {}. UserBet PDA: {}",
+            "User {} bet {} (total {}, tokenized {}) on {:?} for Match #{}. Current slot: {}, Deadline: {:?}. UserBet PDA: {}",
             user_bet.better,
             amount,
+            user_bet.amount,
+            user_bet.tokenized_amount,
             prediction,
             user_bet.match_id,
             clock.slot,
-            betting_pool.betting_deadline_slot,
+            betting_pool.betting_deadline,
             user_bet.key()
         );
         Ok(())
@@ -135,11 +449,14 @@ pub mod solana_rps_arena {
         let clock = Clock::get()?;
 
         require!(
-            betting_pool.status == MatchStatus::OpenForBetting,
+            matches!(
+                betting_pool.status,
+                MatchStatus::OpenForBetting | MatchStatus::Scheduled
+            ),
             RpsError::MatchNotOpenOrAlreadyProcessed
         );
         require!(
-            clock.slot >= betting_pool.betting_deadline_slot,
+            betting_pool.betting_deadline.has_passed(&clock),
             RpsError::BettingDeadlineNotReached
         );
 
@@ -148,23 +465,47 @@ pub mod solana_rps_arena {
             .checked_add(betting_pool.total_ai2_bets)
             .ok_or(RpsError::Overflow)?;
 
-        if total_bets_placed >= betting_pool.min_bet_threshold {
-            betting_pool.status = MatchStatus::AwaitingResolution;
-            msg!("Match #{} deadline passed (Slot {} >= {}). Threshold met ({} >= {}). Now AwaitingResolution.",
-                 betting_pool.match_id, clock.slot, betting_pool.betting_deadline_slot, total_bets_placed, betting_pool.min_bet_threshold);
-        } else {
+        let too_one_sided = betting_pool.imbalance_guard.is_some_and(|guard| {
+            guard.is_exceeded(betting_pool.total_ai1_bets, betting_pool.total_ai2_bets)
+        });
+
+        if total_bets_placed < betting_pool.min_bet_threshold {
             betting_pool.status = MatchStatus::CancelledDueToLowBets;
-            msg!("Match #{} deadline passed (Slot {} >= {}). Threshold NOT met ({} < {}). Cancelled, bets refundable.",
-                 betting_pool.match_id, clock.slot, betting_pool.betting_deadline_slot, total_bets_placed, betting_pool.min_bet_threshold);
+            betting_pool.open_claim_window(clock.slot)?;
+            msg!("Match #{} deadline passed (Slot {}, time {}, deadline {:?}). Threshold NOT met ({} < {}). Cancelled, bets refundable.",
+                 betting_pool.match_id, clock.slot, clock.unix_timestamp, betting_pool.betting_deadline, total_bets_placed, betting_pool.min_bet_threshold);
+        } else if too_one_sided {
+            betting_pool.status = MatchStatus::CancelledDueToImbalance;
+            betting_pool.open_claim_window(clock.slot)?;
+            msg!("Match #{} deadline passed (Slot {}, time {}, deadline {:?}). Pool too one-sided (AI1: {}, AI2: {}, guard {:?}). Cancelled, bets refundable.",
+                 betting_pool.match_id, clock.slot, clock.unix_timestamp, betting_pool.betting_deadline, betting_pool.total_ai1_bets, betting_pool.total_ai2_bets, betting_pool.imbalance_guard);
+        } else {
+            betting_pool.status = MatchStatus::AwaitingResolution;
+            betting_pool.resolution_deadline_slot = clock
+                .slot
+                .checked_add(betting_pool.resolution_timeout_slots)
+                .ok_or(RpsError::Overflow)?;
+            msg!("Match #{} deadline passed (Slot {}, time {}, deadline {:?}). Threshold met ({} >= {}). Now AwaitingResolution.",
+                 betting_pool.match_id, clock.slot, clock.unix_timestamp, betting_pool.betting_deadline, total_bets_placed, betting_pool.min_bet_threshold);
         }
         Ok(())
     } // mutable borrow of betting_pool ends here
 
-    pub fn resolve_match(
+    /// Entrypoint for clients that still send moves as raw bytes (0 = Rock, 1 = Paper,
+    /// 2 = Scissors). New clients should call resolve_match.
+    pub fn resolve_match_legacy(
         ctx: Context<ResolveMatch>,
         ai1_move_raw: u8,
         ai2_move_raw: u8,
     ) -> Result<()> {
+        resolve_match(
+            ctx,
+            Move::from_u8(ai1_move_raw)?,
+            Move::from_u8(ai2_move_raw)?,
+        )
+    }
+
+    pub fn resolve_match(ctx: Context<ResolveMatch>, ai1_move: Move, ai2_move: Move) -> Result<()> {
         // betting_pool is mutably borrowed for status update
         // game_state is mutably borrowed for total_matches update
         // match_record is initialized (effectively a mutable operation)
@@ -174,8 +515,6 @@ pub mod solana_rps_arena {
             RpsError::MatchNotAwaitingResolution
         );
 
-        let ai1_move = Move::from_u8(ai1_move_raw)?;
-        let ai2_move = Move::from_u8(ai2_move_raw)?;
         let winner = crate::determine_rps_winner(ai1_move, ai2_move);
 
         let match_record = &mut ctx.accounts.match_record;
@@ -190,9 +529,16 @@ pub mod solana_rps_arena {
             .total_ai1_bets
             .checked_add(ctx.accounts.betting_pool.total_ai2_bets)
             .ok_or(RpsError::Overflow)?;
+        match_record.version = ACCOUNT_VERSION;
         match_record.bump = ctx.bumps.match_record;
 
-        ctx.accounts.betting_pool.status = MatchStatus::Settled; // Write to betting_pool
+        // Claims stay locked until the challenge window closes (see finalize_match).
+        let clock = Clock::get()?;
+        ctx.accounts.betting_pool.status = MatchStatus::Resolved; // Write to betting_pool
+        ctx.accounts.betting_pool.challenge_deadline_slot = clock
+            .slot
+            .checked_add(ctx.accounts.game_state.dispute_window_slots)
+            .ok_or(RpsError::Overflow)?;
         ctx.accounts.game_state.total_matches = ctx
             .accounts
             .game_state // Write to game_state
@@ -200,405 +546,4264 @@ pub mod solana_rps_arena {
             .checked_add(1)
             .ok_or(RpsError::Overflow)?;
 
-        msg!("Match #{} resolved. AI1: {:?}, AI2: {:?}. Winner: {:?}. Total bets: {}. MatchRecord PDA: {}",
-            match_record.match_id, ai1_move, ai2_move, winner, match_record.total_bet_amount, match_record.key());
+        msg!("Match #{} resolved. AI1: {:?}, AI2: {:?}. Winner: {:?}. Total bets: {}. Challenge window open until slot {}. MatchRecord PDA: {}",
+            match_record.match_id, ai1_move, ai2_move, winner, match_record.total_bet_amount,
+            ctx.accounts.betting_pool.challenge_deadline_slot, match_record.key());
         Ok(())
     }
 
-    pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
-        // Read-only operations first
-        require!(!ctx.accounts.user_bet.claimed, RpsError::AlreadyClaimed);
+    pub fn cancel_unresolved_match(ctx: Context<CancelUnresolvedMatch>) -> Result<()> {
+        let clock = Clock::get()?;
         require!(
-            ctx.accounts.user_bet.match_id == ctx.accounts.betting_pool.match_id,
-            RpsError::MatchIdMismatch
+            ctx.accounts.betting_pool.status == MatchStatus::AwaitingResolution,
+            RpsError::MatchNotAwaitingResolution
+        );
+        require!(
+            clock.slot >= ctx.accounts.betting_pool.resolution_deadline_slot,
+            RpsError::ResolutionTimeoutNotReached
         );
 
-        let mut payout_amount = 0u64;
-
-        // Scope for reading betting_pool status and potentially match_record
-        // All reads from betting_pool here are fine as it's not mutably borrowed *yet* for CPI.
-        match ctx.accounts.betting_pool.status {
-            MatchStatus::Settled => {
-                let match_record = &ctx.accounts.match_record; // immutable borrow of match_record
-                require!(
-                    match_record.match_id == ctx.accounts.betting_pool.match_id,
-                    RpsError::MatchIdMismatchInRecord
-                );
+        let total_bets_placed = ctx
+            .accounts
+            .betting_pool
+            .total_ai1_bets
+            .checked_add(ctx.accounts.betting_pool.total_ai2_bets)
+            .ok_or(RpsError::Overflow)?;
 
-                let user_predicted_winner_type = match ctx.accounts.user_bet.prediction {
-                    Prediction::Ai1 => Winner::Ai1,
-                    Prediction::Ai2 => Winner::Ai2,
-                };
-
-                if match_record.winner == Winner::Draw {
-                    payout_amount = ctx.accounts.user_bet.amount;
-                } else if user_predicted_winner_type == match_record.winner {
-                    let (total_bets_on_winner, total_bets_on_loser) = match match_record.winner {
-                        Winner::Ai1 => (
-                            ctx.accounts.betting_pool.total_ai1_bets,
-                            ctx.accounts.betting_pool.total_ai2_bets,
-                        ),
-                        Winner::Ai2 => (
-                            ctx.accounts.betting_pool.total_ai2_bets,
-                            ctx.accounts.betting_pool.total_ai1_bets,
-                        ),
-                        Winner::Draw => (0, 0),
-                    };
-                    if total_bets_on_winner == 0 {
-                        return err!(RpsError::NoWinningBets);
-                    }
-                    let user_profit = total_bets_on_loser
-                        .checked_mul(ctx.accounts.user_bet.amount)
-                        .ok_or(RpsError::Overflow)?
-                        .checked_div(total_bets_on_winner)
-                        .ok_or(RpsError::DivisionByZero)?;
-                    payout_amount = ctx
-                        .accounts
-                        .user_bet
-                        .amount
-                        .checked_add(user_profit)
-                        .ok_or(RpsError::Overflow)?;
-                } else {
-                    payout_amount = 0;
-                }
-            }
-            MatchStatus::CancelledDueToLowBets => {
-                payout_amount = ctx.accounts.user_bet.amount;
-                msg!(
-                    "Match #{} was cancelled (low bets). Refunding bet of {} to user {}.",
-                    ctx.accounts.user_bet.match_id,
-                    payout_amount,
-                    ctx.accounts.user_bet.better
-                );
-            }
-            _ => return err!(RpsError::MatchNotReadyForClaimOrRefund),
+        // The no-show resolver's bond is shared among the bettors through their refunds.
+        // With nobody to compensate it goes to the treasury instead.
+        let bond = take_resolver_bond(&ctx.accounts.resolver_bond)?;
+        if total_bets_placed > 0 {
+            ctx.accounts.betting_pool.add_lamports(bond)?;
+            ctx.accounts.betting_pool.slashed_bond = bond;
+        } else {
+            ctx.accounts.treasury.add_lamports(bond)?;
+            ctx.accounts.treasury.total_collected = ctx
+                .accounts
+                .treasury
+                .total_collected
+                .checked_add(bond)
+                .ok_or(RpsError::Overflow)?;
         }
+        ctx.accounts.betting_pool.status = MatchStatus::CancelledResolverNoShow;
+        ctx.accounts.betting_pool.open_claim_window(clock.slot)?;
 
-        if payout_amount > 0 {
-            let authority_seeds = &[
-                b"betting_pool_authority".as_ref(),
-                &ctx.accounts.betting_pool.match_id.to_le_bytes(), // Read from betting_pool
-                &[ctx.accounts.betting_pool.pool_authority_bump],  // Read from betting_pool
-            ];
-            let signer_seeds = &[&authority_seeds[..]];
+        msg!(
+            "Match #{} not resolved by slot {} (now {}). Cancelled, bets refundable. Resolver bond of {} lamports slashed.",
+            ctx.accounts.betting_pool.match_id,
+            ctx.accounts.betting_pool.resolution_deadline_slot,
+            clock.slot,
+            bond
+        );
+        Ok(())
+    }
+
+    pub fn release_resolver_bond(ctx: Context<ReleaseResolverBond>) -> Result<()> {
+        // Overturned and no-show matches have already zeroed the bond, so only rent is left.
+        require!(
+            matches!(
+                ctx.accounts.betting_pool.status,
+                MatchStatus::Settled
+                    | MatchStatus::CancelledDueToLowBets
+                    | MatchStatus::CancelledDueToImbalance
+                    | MatchStatus::CancelledResolverNoShow
+            ),
+            RpsError::ResolverBondLocked
+        );
+        msg!(
+            "Match #{} resolver bond of {} lamports released to {}.",
+            ctx.accounts.betting_pool.match_id,
+            ctx.accounts.resolver_bond.amount,
+            ctx.accounts.resolver.key()
+        );
+        Ok(())
+    }
+
+    pub fn finalize_match(ctx: Context<FinalizeMatch>) -> Result<()> {
+        let betting_pool = &mut ctx.accounts.betting_pool;
+        let clock = Clock::get()?;
+
+        require!(
+            betting_pool.status == MatchStatus::Resolved,
+            RpsError::MatchNotResolved
+        );
+        require!(
+            clock.slot >= betting_pool.challenge_deadline_slot,
+            RpsError::ChallengeWindowOpen
+        );
+
+        betting_pool.status = MatchStatus::Settled;
+        betting_pool.open_claim_window(clock.slot)?;
+        msg!(
+            "Match #{} challenge window closed (Slot {} >= {}). Settled, claims unlocked.",
+            betting_pool.match_id,
+            clock.slot,
+            betting_pool.challenge_deadline_slot
+        );
+        Ok(())
+    }
+
+    pub fn raise_dispute(ctx: Context<RaiseDispute>) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            ctx.accounts.betting_pool.status == MatchStatus::Resolved,
+            RpsError::MatchNotResolved
+        );
+        require!(
+            clock.slot < ctx.accounts.betting_pool.challenge_deadline_slot,
+            RpsError::ChallengeWindowClosed
+        );
 
-            let cpi_context_signed = CpiContext::new_with_signer(
+        // The bond sits in the Dispute PDA until adjudicate_dispute returns or slashes it.
+        let bond = ctx.accounts.game_state.dispute_bond_lamports;
+        if bond > 0 {
+            let cpi_context = CpiContext::new(
                 ctx.accounts.system_program.to_account_info(),
                 anchor_lang::system_program::Transfer {
-                    // Immutable borrow of betting_pool's AccountInfo for CPI
-                    from: ctx.accounts.betting_pool.to_account_info(),
-                    to: ctx.accounts.better.to_account_info(),
+                    from: ctx.accounts.disputer.to_account_info(),
+                    to: ctx.accounts.dispute.to_account_info(),
                 },
-                signer_seeds,
             );
-            anchor_lang::system_program::transfer(cpi_context_signed, payout_amount)?;
-            // msg!(/* ... */);
+            anchor_lang::system_program::transfer(cpi_context, bond)?;
         }
 
-        // Mutable borrow of user_bet for update, AFTER all other uses of user_bet (for reads)
-        // and after CPI which doesn't involve user_bet mutably.
-        ctx.accounts.user_bet.claimed = true;
+        let dispute = &mut ctx.accounts.dispute;
+        dispute.match_id = ctx.accounts.betting_pool.match_id;
+        dispute.disputer = ctx.accounts.disputer.key();
+        dispute.bond = bond;
+        dispute.raised_slot = clock.slot;
+        dispute.bump = ctx.bumps.dispute;
+
+        ctx.accounts.betting_pool.status = MatchStatus::Disputed;
+
+        msg!(
+            "Match #{} disputed by {} with bond of {} lamports. Dispute PDA: {}",
+            dispute.match_id,
+            dispute.disputer,
+            dispute.bond,
+            dispute.key()
+        );
         Ok(())
     }
-} // end of #[program] mod
 
-// Helper function moved outside, ensure it's callable (pub if needed by other modules, or just visible here)
-pub fn determine_rps_winner(move1: Move, move2: Move) -> Winner {
-    if move1 == move2 {
-        Winner::Draw
-    } else if (move1 == Move::Rock && move2 == Move::Scissors)
-        || (move1 == Move::Paper && move2 == Move::Rock)
-        || (move1 == Move::Scissors && move2 == Move::Paper)
-    {
-        Winner::Ai1
-    } else {
-        Winner::Ai2
+    /// Pass the corrected moves to overturn the result, or `None` to uphold it and slash
+    /// the disputer's bond.
+    pub fn adjudicate_dispute(
+        ctx: Context<AdjudicateDispute>,
+        corrected_moves: Option<CorrectedMoves>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.betting_pool.status == MatchStatus::Disputed,
+            RpsError::MatchNotDisputed
+        );
+        require!(
+            ctx.accounts.match_record.match_id == ctx.accounts.betting_pool.match_id,
+            RpsError::MatchIdMismatchInRecord
+        );
+
+        if let Some(CorrectedMoves { ai1_move, ai2_move }) = corrected_moves {
+            let match_record = &mut ctx.accounts.match_record;
+            match_record.ai1_move = ai1_move;
+            match_record.ai2_move = ai2_move;
+            match_record.winner = crate::determine_rps_winner(ai1_move, ai2_move);
+            match_record.overturned = true;
+
+            // The resolver published a wrong result, so their bond goes to the treasury.
+            let resolver_bond = take_resolver_bond(&ctx.accounts.resolver_bond)?;
+            ctx.accounts.treasury.add_lamports(resolver_bond)?;
+            ctx.accounts.treasury.total_collected = ctx
+                .accounts
+                .treasury
+                .total_collected
+                .checked_add(resolver_bond)
+                .ok_or(RpsError::Overflow)?;
+
+            msg!(
+                "Match #{} overturned. AI1: {:?}, AI2: {:?}. New winner: {:?}. Bond returned to {}. Resolver bond of {} lamports slashed.",
+                ctx.accounts.match_record.match_id,
+                ai1_move,
+                ai2_move,
+                ctx.accounts.match_record.winner,
+                ctx.accounts.disputer.key(),
+                resolver_bond
+            );
+        } else {
+            // Slash: the bond goes to the treasury; the `close` constraint refunds only rent.
+            let bond = ctx.accounts.dispute.bond;
+            ctx.accounts.dispute.sub_lamports(bond)?;
+            ctx.accounts.treasury.add_lamports(bond)?;
+            ctx.accounts.treasury.total_collected = ctx
+                .accounts
+                .treasury
+                .total_collected
+                .checked_add(bond)
+                .ok_or(RpsError::Overflow)?;
+            msg!(
+                "Match #{} dispute rejected. Bond of {} lamports slashed to treasury.",
+                ctx.accounts.betting_pool.match_id,
+                bond
+            );
+        }
+
+        ctx.accounts.betting_pool.status = MatchStatus::Settled;
+        ctx.accounts
+            .betting_pool
+            .open_claim_window(Clock::get()?.slot)?;
+        Ok(())
     }
-}
 
-// --- ACCOUNTS CONTEXTS ---
-#[derive(Accounts)]
-pub struct InitializeGame<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + GameState::LEN,
-        seeds = [b"game_state".as_ref()],
-        bump
-    )]
-    pub game_state: Account<'info, GameState>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        let treasury_info = ctx.accounts.treasury.to_account_info();
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(treasury_info.data_len());
+        let available = treasury_info.lamports().saturating_sub(rent_exempt_minimum);
+        require!(amount <= available, RpsError::InsufficientTreasuryFunds);
 
-#[derive(Accounts)]
-#[instruction(min_bet_threshold_lamports: u64, betting_duration_slots: u64)]
-pub struct CreateMatch<'info> {
-    #[account(
-        mut,
-        seeds = [b"game_state".as_ref()],
-        bump = game_state.bump
-    )]
-    pub game_state: Account<'info, GameState>,
-    #[account(
-        init,
-        payer = match_creator_signer,
-        space = 8 + BettingPool::LEN,
-        seeds = [b"betting_pool".as_ref(), &game_state.next_match_id.to_le_bytes()],
-        bump
-    )]
-    pub betting_pool: Account<'info, BettingPool>,
-    #[account(
-        seeds = [b"betting_pool_authority".as_ref(), &game_state.next_match_id.to_le_bytes()],
-        bump
-    )]
-    /// CHECK: PDA authority, no data stored.
-    pub betting_pool_authority: AccountInfo<'info>,
-    #[account(mut)]
-    pub match_creator_signer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        ctx.accounts.treasury.sub_lamports(amount)?;
+        ctx.accounts.recipient.add_lamports(amount)?;
 
-#[derive(Accounts)]
-#[instruction(amount: u64, prediction_raw: u8)]
-pub struct PlaceBet<'info> {
-    #[account(
-        mut, // betting_pool is mutable here because its fields total_ai1_bets etc. are updated AFTER CPI
-        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
-        bump = betting_pool.bump
-    )]
-    pub betting_pool: Account<'info, BettingPool>,
-    #[account(
-        init,
-        payer = better,
-        space = 8 + UserBet::LEN,
-        seeds = [
-            b"user_bet".as_ref(),
-            better.key().as_ref(),
-            &betting_pool.match_id.to_le_bytes() // This read from betting_pool is fine
-        ],
+        msg!(
+            "Withdrew {} lamports from treasury to {}.",
+            amount,
+            ctx.accounts.recipient.key()
+        );
+        Ok(())
+    }
+
+    pub fn initialize_house_vault(
+        ctx: Context<InitializeHouseVault>,
+        withdrawal_cooldown_slots: u64,
+    ) -> Result<()> {
+        let house_vault = &mut ctx.accounts.house_vault;
+        house_vault.total_liability = 0;
+        house_vault.bump = ctx.bumps.house_vault;
+        house_vault.lp_mint = ctx.accounts.lp_mint.key();
+        house_vault.withdrawal_cooldown_slots = withdrawal_cooldown_slots;
+        house_vault.total_fees_collected = 0;
+        msg!(
+            "House vault initialized. House vault PDA: {}. LP mint: {}. Withdrawal cooldown: {} slots.",
+            house_vault.key(),
+            house_vault.lp_mint,
+            house_vault.withdrawal_cooldown_slots
+        );
+        Ok(())
+    }
+
+    pub fn deposit_liquidity(ctx: Context<DepositLiquidity>, amount: u64) -> Result<()> {
+        require!(amount > 0, RpsError::DepositAmountZero);
+
+        // Price shares against the vault before this deposit lands.
+        let net_assets = ctx
+            .accounts
+            .house_vault
+            .net_assets(&ctx.accounts.house_vault.to_account_info())?;
+        let share_supply = ctx.accounts.lp_mint.supply;
+        let shares = lp_shares_for_deposit(amount, net_assets, share_supply)?;
+        require!(shares > 0, RpsError::DepositAmountZero);
+
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.depositor.to_account_info(),
+                to: ctx.accounts.house_vault.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, amount)?;
+
+        let vault_seeds = &[b"house_vault".as_ref(), &[ctx.accounts.house_vault.bump]];
+        let signer_seeds = &[&vault_seeds[..]];
+        if share_supply == 0 {
+            // The escrow only ever releases shares recorded in a withdrawal, so these
+            // stay locked for good.
+            token::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::MintTo {
+                        mint: ctx.accounts.lp_mint.to_account_info(),
+                        to: ctx.accounts.lp_escrow.to_account_info(),
+                        authority: ctx.accounts.house_vault.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                LOCKED_LP_SHARES,
+            )?;
+        }
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.lp_mint.to_account_info(),
+                    to: ctx.accounts.depositor_lp_account.to_account_info(),
+                    authority: ctx.accounts.house_vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            shares,
+        )?;
+
+        msg!(
+            "{} deposited {} lamports into the house vault for {} LP shares (net assets before: {}).",
+            ctx.accounts.depositor.key(),
+            amount,
+            shares,
+            net_assets
+        );
+        Ok(())
+    }
+
+    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, shares: u64) -> Result<()> {
+        require!(shares > 0, RpsError::WithdrawalAmountZero);
+        let clock = Clock::get()?;
+
+        // Shares sit in escrow during the cooldown so they keep carrying the vault's risk.
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.owner_lp_account.to_account_info(),
+                    to: ctx.accounts.lp_escrow.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+
+        let withdrawal = &mut ctx.accounts.withdrawal;
+        withdrawal.owner = ctx.accounts.owner.key();
+        withdrawal.shares = shares;
+        withdrawal.unlock_slot = clock
+            .slot
+            .checked_add(ctx.accounts.house_vault.withdrawal_cooldown_slots)
+            .ok_or(RpsError::Overflow)?;
+        withdrawal.bump = ctx.bumps.withdrawal;
+
+        msg!(
+            "{} requested withdrawal of {} LP shares, unlocking at slot {}.",
+            withdrawal.owner,
+            withdrawal.shares,
+            withdrawal.unlock_slot
+        );
+        Ok(())
+    }
+
+    pub fn cancel_withdrawal(ctx: Context<CancelWithdrawal>) -> Result<()> {
+        let vault_seeds = &[b"house_vault".as_ref(), &[ctx.accounts.house_vault.bump]];
+        let signer_seeds = &[&vault_seeds[..]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.lp_escrow.to_account_info(),
+                    to: ctx.accounts.owner_lp_account.to_account_info(),
+                    authority: ctx.accounts.house_vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            ctx.accounts.withdrawal.shares,
+        )?;
+        msg!(
+            "{} cancelled withdrawal of {} LP shares.",
+            ctx.accounts.owner.key(),
+            ctx.accounts.withdrawal.shares
+        );
+        Ok(())
+    }
+
+    pub fn complete_withdrawal(ctx: Context<CompleteWithdrawal>) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            clock.slot >= ctx.accounts.withdrawal.unlock_slot,
+            RpsError::WithdrawalCooldownActive
+        );
+
+        let shares = ctx.accounts.withdrawal.shares;
+        let net_assets = ctx
+            .accounts
+            .house_vault
+            .net_assets(&ctx.accounts.house_vault.to_account_info())?;
+        let lamports = lp_shares_value(shares, net_assets, ctx.accounts.lp_mint.supply)?;
+
+        let vault_seeds = &[b"house_vault".as_ref(), &[ctx.accounts.house_vault.bump]];
+        let signer_seeds = &[&vault_seeds[..]];
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    mint: ctx.accounts.lp_mint.to_account_info(),
+                    from: ctx.accounts.lp_escrow.to_account_info(),
+                    authority: ctx.accounts.house_vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            shares,
+        )?;
+
+        // net_assets already excludes reserved liabilities and rent, so this cannot
+        // touch funds owed to open bets.
+        ctx.accounts.house_vault.sub_lamports(lamports)?;
+        ctx.accounts.owner.add_lamports(lamports)?;
+
+        msg!(
+            "{} withdrew {} lamports for {} LP shares (net assets before: {}).",
+            ctx.accounts.owner.key(),
+            lamports,
+            shares,
+            net_assets
+        );
+        Ok(())
+    }
+
+    pub fn collect_pool_fees(ctx: Context<CollectPoolFees>) -> Result<()> {
+        let betting_pool = &mut ctx.accounts.betting_pool;
+        let uncollected = move_fees_to_vault(betting_pool, &mut ctx.accounts.house_vault)?;
+        require!(uncollected > 0, RpsError::NoFeesToCollect);
+
+        msg!(
+            "Collected {} lamports of fees from Match #{} into the house vault.",
+            uncollected,
+            betting_pool.match_id
+        );
+        Ok(())
+    }
+
+    pub fn release_house_liability(ctx: Context<ReleaseHouseLiability>) -> Result<()> {
+        require!(
+            ctx.accounts.betting_pool.market.is_fixed_odds(),
+            RpsError::NotFixedOddsMarket
+        );
+        require!(
+            !ctx.accounts.betting_pool.house_liability_settled,
+            RpsError::HouseLiabilityAlreadySettled
+        );
+
+        let outcome = match ctx.accounts.betting_pool.status {
+            MatchStatus::Settled => {
+                let match_record = ctx
+                    .accounts
+                    .match_record
+                    .as_ref()
+                    .ok_or(RpsError::MatchRecordRequired)?;
+                require!(
+                    match_record.match_id == ctx.accounts.betting_pool.match_id,
+                    RpsError::MatchIdMismatchInRecord
+                );
+                Some(match_record.winner)
+            }
+            MatchStatus::CancelledDueToLowBets
+            | MatchStatus::CancelledDueToImbalance
+            | MatchStatus::CancelledResolverNoShow => None,
+            _ => return err!(RpsError::MatchNotReadyForClaimOrRefund),
+        };
+
+        let betting_pool = &mut ctx.accounts.betting_pool;
+        let house_vault = &mut ctx.accounts.house_vault;
+        let released = betting_pool.house_reserved;
+        let owed = betting_pool.final_house_liability(outcome)?;
+        set_house_reservation(betting_pool, house_vault, owed)?;
+        betting_pool.house_liability_settled = true;
+
+        msg!(
+            "Match #{} house reservation trued up from {} to {} lamports. Vault liability now {}.",
+            betting_pool.match_id,
+            released,
+            owed,
+            house_vault.total_liability
+        );
+        Ok(())
+    }
+
+    pub fn enable_position_tokens(ctx: Context<EnablePositionTokens>) -> Result<()> {
+        let betting_pool = &mut ctx.accounts.betting_pool;
+        require!(
+            matches!(
+                betting_pool.status,
+                MatchStatus::Scheduled | MatchStatus::OpenForBetting
+            ),
+            RpsError::BettingClosedOrNotOpen
+        );
+        // Fixed-odds payouts run through the house vault's per-bet liability tracking,
+        // which position tokens would bypass.
+        require!(
+            !betting_pool.market.is_fixed_odds(),
+            RpsError::PositionTokensUnsupported
+        );
+
+        betting_pool.position_tokens_enabled = true;
+        msg!(
+            "Match #{} position tokens enabled. AI1 mint: {}, AI2 mint: {}.",
+            betting_pool.match_id,
+            ctx.accounts.ai1_position_mint.key(),
+            ctx.accounts.ai2_position_mint.key()
+        );
+        Ok(())
+    }
+
+    pub fn redeem_position(
+        ctx: Context<RedeemPosition>,
+        side: Prediction,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, RpsError::RedeemAmountZero);
+        let claim = compute_claim(
+            &ctx.accounts.betting_pool,
+            ctx.accounts.match_record.as_deref(),
+            side,
+            amount,
+            0,
+        )?;
+
+        // Losing positions can still be burned; they simply pay nothing.
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Burn {
+                    mint: ctx.accounts.position_mint.to_account_info(),
+                    from: ctx.accounts.holder_token_account.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        // Position tokens only exist on parimutuel pools, so the house vault is never needed.
+        debit_claim(&mut ctx.accounts.betting_pool, None, &claim)?;
+        ctx.accounts.holder.add_lamports(claim.payout)?;
+        emit!(PositionRedeemed {
+            match_id: ctx.accounts.betting_pool.match_id,
+            position_mint: ctx.accounts.position_mint.key(),
+            holder: ctx.accounts.holder.key(),
+            side,
+            amount,
+            payout: claim.payout,
+            fee: claim.fee,
+            stake: claim.stake,
+            bond_share: claim.bond_share,
+        });
+
+        msg!(
+            "Match #{} {:?}: {} redeemed {} {:?} position tokens for {} lamports ({} fee).",
+            ctx.accounts.betting_pool.match_id,
+            ctx.accounts.betting_pool.status,
+            ctx.accounts.holder.key(),
+            amount,
+            side,
+            claim.payout,
+            claim.fee
+        );
+        Ok(())
+    }
+
+    pub fn list_bet(ctx: Context<ListBet>, price_lamports: u64) -> Result<()> {
+        ctx.accounts
+            .user_bet
+            .require_tradable(ctx.accounts.betting_pool.status)?;
+
+        let listing = &mut ctx.accounts.listing;
+        listing.user_bet = ctx.accounts.user_bet.key();
+        listing.seller = ctx.accounts.seller.key();
+        listing.price_lamports = price_lamports;
+        listing.listed_amount = ctx.accounts.user_bet.amount;
+        listing.bump = ctx.bumps.listing;
+
+        msg!(
+            "{} listed bet {} ({} lamports on {:?}, Match #{}) for {} lamports. Listing PDA: {}",
+            listing.seller,
+            listing.user_bet,
+            listing.listed_amount,
+            ctx.accounts.user_bet.prediction,
+            ctx.accounts.user_bet.match_id,
+            listing.price_lamports,
+            listing.key()
+        );
+        Ok(())
+    }
+
+    pub fn cancel_listing(ctx: Context<CancelListing>) -> Result<()> {
+        msg!(
+            "{} cancelled listing for bet {}.",
+            ctx.accounts.seller.key(),
+            ctx.accounts.listing.user_bet
+        );
+        Ok(())
+    }
+
+    /// Bought bets are not counted against the buyer's `BetLimits::max_per_user`; the cap
+    /// applied when the bet was placed.
+    pub fn buy_bet(ctx: Context<BuyBet>) -> Result<()> {
+        ctx.accounts
+            .user_bet
+            .require_tradable(ctx.accounts.betting_pool.status)?;
+        ctx.accounts
+            .listing
+            .require_buyable(&ctx.accounts.user_bet, &ctx.accounts.buyer.key())?;
+
+        let price = ctx.accounts.listing.price_lamports;
+        if price > 0 {
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: ctx.accounts.seller.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, price)?;
+        }
+
+        ctx.accounts.user_bet.better = ctx.accounts.buyer.key();
+        msg!(
+            "{} bought bet {} ({} lamports on {:?}, Match #{}) from {} for {} lamports.",
+            ctx.accounts.buyer.key(),
+            ctx.accounts.user_bet.key(),
+            ctx.accounts.user_bet.amount,
+            ctx.accounts.user_bet.prediction,
+            ctx.accounts.user_bet.match_id,
+            ctx.accounts.seller.key(),
+            price
+        );
+        Ok(())
+    }
+
+    pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
+        // Read-only operations first
+        ctx.accounts.user_bet.require_claimable()?;
+        require!(
+            ctx.accounts.user_bet.match_id == ctx.accounts.betting_pool.match_id,
+            RpsError::MatchIdMismatch
+        );
+
+        let claim = compute_claim(
+            &ctx.accounts.betting_pool,
+            ctx.accounts.match_record.as_deref(),
+            ctx.accounts.user_bet.prediction,
+            ctx.accounts.user_bet.amount,
+            ctx.accounts.user_bet.locked_odds_bps,
+        )?;
+        let house_payout = debit_claim(
+            &mut ctx.accounts.betting_pool,
+            ctx.accounts.house_vault.as_mut(),
+            &claim,
+        )?;
+        ctx.accounts.better.add_lamports(claim.payout)?;
+        emit!(ClaimPaid::new(
+            ctx.accounts.user_bet.match_id,
+            ctx.accounts.user_bet.key(),
+            ctx.accounts.better.key(),
+            &claim,
+        ));
+        msg!(
+            "Match #{} {:?}: paid {} lamports ({} from house vault, {} fee, {} bond share) to {}.",
+            ctx.accounts.user_bet.match_id,
+            ctx.accounts.betting_pool.status,
+            claim.payout,
+            house_payout,
+            claim.fee,
+            claim.bond_share,
+            ctx.accounts.better.key()
+        );
+
+        // Mutable borrow of user_bet for update, AFTER all other uses of user_bet (for reads)
+        // and after CPI which doesn't involve user_bet mutably.
+        ctx.accounts.user_bet.claimed = true;
+        Ok(())
+    }
+
+    /// Claims several bets at once. `remaining_accounts` holds one
+    /// `(betting_pool, match_record, user_bet)` triple per bet. Pass the program ID in
+    /// place of the match record for cancelled matches.
+    pub fn claim_many<'info>(ctx: Context<'_, '_, 'info, 'info, ClaimMany<'info>>) -> Result<()> {
+        let remaining_accounts = ctx.remaining_accounts;
+        require!(
+            !remaining_accounts.is_empty() && remaining_accounts.len().is_multiple_of(3),
+            RpsError::InvalidClaimAccounts
+        );
+
+        let better_key = ctx.accounts.better.key();
+        let mut total_payout = 0u64;
+        for triple in remaining_accounts.chunks(3) {
+            let payout = claim_triple(triple, better_key, ctx.accounts.house_vault.as_mut())?;
+            total_payout = total_payout.checked_add(payout).ok_or(RpsError::Overflow)?;
+        }
+
+        ctx.accounts.better.add_lamports(total_payout)?;
+        msg!(
+            "Claimed {} bets for {} in one transaction, paying {} lamports.",
+            remaining_accounts.len() / 3,
+            better_key,
+            total_payout
+        );
+        Ok(())
+    }
+
+    /// Permissionless crank that pushes payouts to bettors once a match can be claimed.
+    /// `remaining_accounts` holds one `(user_bet, owner)` pair per bet for this pool.
+    /// Each paid bet earns the keeper up to KEEPER_REWARD_LAMPORTS out of the pool's
+    /// uncollected fees; bettors always receive their full payout.
+    /// Bets already claimed are skipped so pages can overlap with manual claims, and so
+    /// are bets held only as position tokens.
+    pub fn distribute<'info>(ctx: Context<'_, '_, 'info, 'info, Distribute<'info>>) -> Result<()> {
+        let remaining_accounts = ctx.remaining_accounts;
+        require!(
+            !remaining_accounts.is_empty() && remaining_accounts.len().is_multiple_of(2),
+            RpsError::InvalidDistributeAccounts
+        );
+
+        let match_id = ctx.accounts.betting_pool.match_id;
+        let mut paid_count = 0u64;
+        let mut total_paid = 0u64;
+        let mut keeper_reward = 0u64;
+        for pair in remaining_accounts.chunks(2) {
+            let (user_bet_info, owner_info) = (&pair[0], &pair[1]);
+            require!(
+                user_bet_info.is_writable && owner_info.is_writable,
+                RpsError::InvalidDistributeAccounts
+            );
+
+            let mut user_bet = Account::<UserBet>::try_from(user_bet_info)?;
+            verify_pda(
+                user_bet_info,
+                &[
+                    b"user_bet".as_ref(),
+                    user_bet.origin.as_ref(),
+                    &user_bet.match_id.to_le_bytes(),
+                ],
+                user_bet.bump,
+            )?;
+            require!(user_bet.match_id == match_id, RpsError::MatchIdMismatch);
+            require_keys_eq!(
+                owner_info.key(),
+                user_bet.better,
+                RpsError::UserBetOwnerMismatch
+            );
+            if user_bet.require_claimable().is_err() {
+                continue;
+            }
+
+            let claim = compute_claim(
+                &ctx.accounts.betting_pool,
+                ctx.accounts.match_record.as_deref(),
+                user_bet.prediction,
+                user_bet.amount,
+                user_bet.locked_odds_bps,
+            )?;
+            debit_claim(
+                &mut ctx.accounts.betting_pool,
+                ctx.accounts.house_vault.as_mut(),
+                &claim,
+            )?;
+            user_bet.claimed = true;
+            // Persist now so the same bet listed twice in a page is skipped the second time.
+            user_bet.exit(&crate::ID)?;
+            owner_info.add_lamports(claim.payout)?;
+
+            let reward = take_keeper_reward(&mut ctx.accounts.betting_pool)?;
+            emit!(ClaimPaid {
+                keeper_reward: reward,
+                ..ClaimPaid::new(match_id, user_bet_info.key(), owner_info.key(), &claim)
+            });
+            keeper_reward = keeper_reward
+                .checked_add(reward)
+                .ok_or(RpsError::Overflow)?;
+            total_paid = total_paid
+                .checked_add(claim.payout)
+                .ok_or(RpsError::Overflow)?;
+            paid_count += 1;
+        }
+
+        ctx.accounts.betting_pool.sub_lamports(keeper_reward)?;
+        ctx.accounts.keeper.add_lamports(keeper_reward)?;
+        msg!(
+            "Match #{}: distributed {} lamports across {} bets; keeper {} earned {} lamports.",
+            match_id,
+            total_paid,
+            paid_count,
+            ctx.accounts.keeper.key(),
+            keeper_reward
+        );
+        Ok(())
+    }
+
+    /// Once the claim window has passed, hands whatever is left in the pool (unclaimed
+    /// payouts and rounding dust) to the treasury and closes the pool. Uncollected fees
+    /// still go to the house vault, and a fixed-odds pool frees its vault reservation.
+    pub fn sweep_unclaimed(ctx: Context<SweepUnclaimed>) -> Result<()> {
+        let clock = Clock::get()?;
+        let betting_pool = &mut ctx.accounts.betting_pool;
+        betting_pool.require_sweepable(clock.slot)?;
+
+        let needs_vault = betting_pool.market.is_fixed_odds()
+            || betting_pool.fees_accrued > betting_pool.fees_collected;
+        let mut fees = 0;
+        if needs_vault {
+            let house_vault = ctx
+                .accounts
+                .house_vault
+                .as_mut()
+                .ok_or(RpsError::HouseVaultRequired)?;
+            fees = move_fees_to_vault(betting_pool, house_vault)?;
+            // Unclaimed fixed-odds winnings never left the vault; stop reserving them.
+            if betting_pool.market.is_fixed_odds() {
+                set_house_reservation(betting_pool, house_vault, 0)?;
+                betting_pool.house_liability_settled = true;
+            }
+        }
+
+        // The `close` constraint moves the remaining balance, rent included, to the treasury.
+        let swept = betting_pool.to_account_info().lamports();
+        ctx.accounts.treasury.total_collected = ctx
+            .accounts
+            .treasury
+            .total_collected
+            .checked_add(swept)
+            .ok_or(RpsError::Overflow)?;
+
+        msg!(
+            "Match #{} claim window closed at slot {} (now {}). Swept {} lamports to treasury, {} lamports of fees to house vault. Pool closed.",
+            ctx.accounts.betting_pool.match_id,
+            ctx.accounts.betting_pool.claim_deadline_slot,
+            clock.slot,
+            swept,
+            fees
+        );
+        Ok(())
+    }
+
+    /// Rewrites a version 0 GameState in the current layout and creates the treasury,
+    /// which did not exist yet. Takes the configuration initialize_game now requires.
+    pub fn migrate_game_state(ctx: Context<MigrateGameState>, config: GameConfig) -> Result<()> {
+        config.validate()?;
+        let legacy: GameStateV0 = load_legacy_account(
+            &ctx.accounts.game_state,
+            GameState::DISCRIMINATOR,
+            GameStateV0::LEN,
+        )?;
+        require_keys_eq!(
+            legacy.authority,
+            ctx.accounts.authority.key(),
+            RpsError::Unauthorized
+        );
+
+        let game_state = legacy.migrate(&config);
+        write_migrated_account(
+            &ctx.accounts.game_state,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            8 + GameState::INIT_SPACE,
+            &game_state,
+        )?;
+
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.total_collected = 0;
+        treasury.bump = ctx.bumps.treasury;
+
+        msg!(
+            "GameState migrated to version {}. Next Match ID: {}. Treasury PDA: {}",
+            ACCOUNT_VERSION,
+            game_state.next_match_id,
+            treasury.key()
+        );
+        Ok(())
+    }
+
+    /// Rewrites a version 0 BettingPool in the current layout. Anyone may pay for this;
+    /// the result only depends on the old account and the (already migrated) GameState.
+    /// `remaining_accounts` holds every bet already claimed from the pool, in ascending
+    /// address order, so the claim progress carries over. Settled pools with claims also
+    /// need their (already migrated) match record.
+    pub fn migrate_betting_pool<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MigrateBettingPool<'info>>,
+    ) -> Result<()> {
+        let legacy: BettingPoolV0 = load_legacy_account(
+            &ctx.accounts.betting_pool,
+            BettingPool::DISCRIMINATOR,
+            BettingPoolV0::LEN,
+        )?;
+        let paid_out = legacy.paid_out(ctx.accounts.betting_pool.lamports(), &Rent::get()?)?;
+        let claimed = load_claimed_legacy_bets(ctx.remaining_accounts, legacy.match_id)?;
+        let winner = match &ctx.accounts.match_record {
+            Some(match_record) => {
+                require!(
+                    match_record.match_id == legacy.match_id,
+                    RpsError::MatchIdMismatchInRecord
+                );
+                Some(match_record.winner)
+            }
+            None => None,
+        };
+
+        let betting_pool = legacy.migrate(
+            &ctx.accounts.game_state,
+            Clock::get()?.slot,
+            winner,
+            &claimed,
+        )?;
+        // Leaving out a claimed bet would understate what has already left the pool.
+        require!(
+            betting_pool.total_paid_out >= paid_out,
+            RpsError::ClaimedBetsMissing
+        );
+        write_migrated_account(
+            &ctx.accounts.betting_pool,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            8 + BettingPool::INIT_SPACE,
+            &betting_pool,
+        )?;
+        msg!(
+            "Match #{} BettingPool migrated to version {}. Status: {:?}.",
+            betting_pool.match_id,
+            ACCOUNT_VERSION,
+            betting_pool.status
+        );
+        Ok(())
+    }
+
+    /// Rewrites a version 0 MatchRecord in the current layout.
+    pub fn migrate_match_record(ctx: Context<MigrateAccount>) -> Result<()> {
+        let legacy: MatchRecordV0 = load_legacy_account(
+            &ctx.accounts.account,
+            MatchRecord::DISCRIMINATOR,
+            MatchRecordV0::LEN,
+        )?;
+        let match_record = legacy.migrate();
+        write_migrated_account(
+            &ctx.accounts.account,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            8 + MatchRecord::INIT_SPACE,
+            &match_record,
+        )?;
+        msg!(
+            "Match #{} MatchRecord migrated to version {}.",
+            match_record.match_id,
+            ACCOUNT_VERSION
+        );
+        Ok(())
+    }
+
+    /// Rewrites a version 0 UserBet in the current layout.
+    pub fn migrate_user_bet(ctx: Context<MigrateAccount>) -> Result<()> {
+        let legacy: UserBetV0 = load_legacy_account(
+            &ctx.accounts.account,
+            UserBet::DISCRIMINATOR,
+            UserBetV0::LEN,
+        )?;
+        let user_bet = legacy.migrate();
+        write_migrated_account(
+            &ctx.accounts.account,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            8 + UserBet::INIT_SPACE,
+            &user_bet,
+        )?;
+        msg!(
+            "Match #{} UserBet of {} migrated to version {}.",
+            user_bet.match_id,
+            user_bet.better,
+            ACCOUNT_VERSION
+        );
+        Ok(())
+    }
+} // end of #[program] mod
+
+// Helper function moved outside, ensure it's callable (pub if needed by other modules, or just visible here)
+pub fn determine_rps_winner(move1: Move, move2: Move) -> Winner {
+    if move1 == move2 {
+        Winner::Draw
+    } else if (move1 == Move::Rock && move2 == Move::Scissors)
+        || (move1 == Move::Paper && move2 == Move::Rock)
+        || (move1 == Move::Scissors && move2 == Move::Paper)
+    {
+        Winner::Ai1
+    } else {
+        Winner::Ai2
+    }
+}
+
+/// What a position of `amount` lamports on one side is owed from a claimable pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClaimAmounts {
+    /// Total lamports owed to the holder, stake included.
+    pub payout: u64,
+    /// Platform fee withheld from the payout. It stays in the pool for collect_pool_fees.
+    pub fee: u64,
+    /// Part of `payout` that is a slashed resolver bond share, always paid from the pool.
+    pub bond_share: u64,
+    /// Part of `payout` that returns the holder's own stake; zero for losing bets.
+    pub stake: u64,
+    /// Final result for settled matches, `None` for cancellations.
+    pub outcome: Option<Winner>,
+}
+
+/// Logged for every bet paid by claim_winnings, claim_many or distribute, so indexers
+/// can record exactly what was paid instead of re-deriving it.
+#[event]
+pub struct ClaimPaid {
+    pub match_id: u64,
+    pub user_bet: Pubkey,
+    pub recipient: Pubkey,
+    pub payout: u64,
+    pub fee: u64,
+    pub stake: u64,
+    pub bond_share: u64,
+    /// Paid by distribute to its keeper out of the pool's fees, never out of `payout`.
+    pub keeper_reward: u64,
+}
+
+impl ClaimPaid {
+    fn new(match_id: u64, user_bet: Pubkey, recipient: Pubkey, claim: &ClaimAmounts) -> Self {
+        Self {
+            match_id,
+            user_bet,
+            recipient,
+            payout: claim.payout,
+            fee: claim.fee,
+            stake: claim.stake,
+            bond_share: claim.bond_share,
+            keeper_reward: 0,
+        }
+    }
+}
+
+/// Logged for every redeem_position call.
+#[event]
+pub struct PositionRedeemed {
+    pub match_id: u64,
+    pub position_mint: Pubkey,
+    pub holder: Pubkey,
+    pub side: Prediction,
+    /// Position tokens burned.
+    pub amount: u64,
+    pub payout: u64,
+    pub fee: u64,
+    pub stake: u64,
+    pub bond_share: u64,
+}
+
+/// Shared payout rules for claim_winnings and position token redemption. The pure
+/// parimutuel rules live in `settlement`; this adds fixed odds, dust and bond shares.
+/// Public so clients can show exactly what a claim will pay.
+pub fn compute_claim(
+    betting_pool: &BettingPool,
+    match_record: Option<&MatchRecord>,
+    prediction: Prediction,
+    amount: u64,
+    locked_odds_bps: u64,
+) -> Result<ClaimAmounts> {
+    let mut claim = ClaimAmounts::default();
+    let outcome = match betting_pool.status {
+        MatchStatus::Settled => {
+            let match_record = match_record.ok_or(RpsError::MatchRecordRequired)?;
+            require!(
+                match_record.match_id == betting_pool.match_id,
+                RpsError::MatchIdMismatchInRecord
+            );
+            claim.outcome = Some(match_record.winner);
+            Outcome::Settled(match_record.winner)
+        }
+        MatchStatus::CancelledDueToLowBets
+        | MatchStatus::CancelledDueToImbalance
+        | MatchStatus::CancelledResolverNoShow => Outcome::Refunded,
+        _ => return err!(RpsError::MatchNotReadyForClaimOrRefund),
+    };
+
+    let totals = PoolTotals {
+        ai1: betting_pool.total_ai1_bets,
+        ai2: betting_pool.total_ai2_bets,
+    };
+    let bet = Bet { prediction, amount };
+    let settlement = match outcome {
+        Outcome::Settled(winner)
+            if predicted(prediction, winner) && betting_pool.market.is_fixed_odds() =>
+        {
+            Settlement {
+                payout: fixed_odds_payout(amount, locked_odds_bps)?,
+                fee: 0,
+                stake: amount,
+            }
+        }
+        Outcome::Settled(winner) if predicted(prediction, winner) => {
+            let (total_bets_on_winner, total_bets_on_loser) =
+                totals.split(winner).unwrap_or_default();
+            // Floor division leaves dust behind, so the last winning claim takes
+            // whatever profit is left instead of its pro-rata share.
+            if betting_pool.is_last_claim(amount, total_bets_on_winner)? {
+                let profit_paid = betting_pool
+                    .total_paid_out
+                    .checked_add(betting_pool.fees_accrued)
+                    .and_then(|paid| paid.checked_sub(betting_pool.claimed_stake))
+                    .ok_or(RpsError::Overflow)?;
+                let remaining_profit = total_bets_on_loser
+                    .checked_sub(profit_paid)
+                    .ok_or(RpsError::PayoutExceedsDeposits)?;
+                let payout =
+                    payout::payout_with_profit(amount, remaining_profit, betting_pool.fee_bps)?;
+                Settlement {
+                    payout: payout.total,
+                    fee: payout.fee,
+                    stake: amount,
+                }
+            } else {
+                settle(totals, outcome, bet, betting_pool.fee_bps)?
+            }
+        }
+        _ => settle(totals, outcome, bet, betting_pool.fee_bps)?,
+    };
+    claim.payout = settlement.payout;
+    claim.fee = settlement.fee;
+    claim.stake = settlement.stake;
+
+    if betting_pool.status == MatchStatus::CancelledResolverNoShow {
+        // As above, the last refund takes the rest of the slashed bond.
+        claim.bond_share = if betting_pool.is_last_claim(amount, betting_pool.total_bets()?)? {
+            let bond_paid = betting_pool
+                .total_paid_out
+                .checked_sub(betting_pool.claimed_stake)
+                .ok_or(RpsError::Overflow)?;
+            betting_pool
+                .slashed_bond
+                .checked_sub(bond_paid)
+                .ok_or(RpsError::PayoutExceedsDeposits)?
+        } else {
+            payout::pro_rata(
+                betting_pool.slashed_bond,
+                amount,
+                betting_pool.total_bets()?,
+            )?
+        };
+        claim.payout = claim
+            .payout
+            .checked_add(claim.bond_share)
+            .ok_or(RpsError::Overflow)?;
+    }
+    Ok(claim)
+}
+
+/// Takes the lamports owed by `claim` out of the pool, or out of the house vault for
+/// fixed-odds markets, and books the fee. The caller credits `claim.payout` to the
+/// recipient. Returns the part paid by the house vault.
+fn debit_claim(
+    betting_pool: &mut Account<BettingPool>,
+    house_vault: Option<&mut Account<HouseVault>>,
+    claim: &ClaimAmounts,
+) -> Result<u64> {
+    betting_pool.require_claim_window_open(Clock::get()?.slot)?;
+
+    // Fixed-odds stakes and winnings are paid by the house vault. Only a slashed
+    // resolver bond share ever comes out of the pool itself for those markets.
+    let house_payout = if betting_pool.market.is_fixed_odds() {
+        let house_vault = house_vault.ok_or(RpsError::HouseVaultRequired)?;
+        if !betting_pool.house_liability_settled {
+            let owed = betting_pool.final_house_liability(claim.outcome)?;
+            set_house_reservation(betting_pool, house_vault, owed)?;
+            betting_pool.house_liability_settled = true;
+        }
+        let house_payout = claim
+            .payout
+            .checked_sub(claim.bond_share)
+            .ok_or(RpsError::Overflow)?;
+        let still_reserved = betting_pool
+            .house_reserved
+            .checked_sub(house_payout)
+            .ok_or(RpsError::Overflow)?;
+        set_house_reservation(betting_pool, house_vault, still_reserved)?;
+        house_vault.sub_lamports(house_payout)?;
+        house_payout
+    } else {
+        0
+    };
+    let pool_payout = claim
+        .payout
+        .checked_sub(house_payout)
+        .ok_or(RpsError::Overflow)?;
+
+    // The pool and vault are program-owned, so lamports are moved directly rather than
+    // through a system program transfer. Fees stay in the pool until collect_pool_fees.
+    betting_pool.sub_lamports(pool_payout)?;
+    betting_pool.record_claim(claim)?;
+    Ok(house_payout)
+}
+
+/// Empties the resolver bond at `info` and returns the lamports taken out of it, which
+/// the caller credits elsewhere. A bond account that was never created holds nothing.
+fn take_resolver_bond(info: &AccountInfo) -> Result<u64> {
+    if info.data_is_empty() {
+        return Ok(0);
+    }
+    require_keys_eq!(
+        *info.owner,
+        crate::ID,
+        ErrorCode::AccountOwnedByWrongProgram
+    );
+    let mut resolver_bond = ResolverBond::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    let bond = resolver_bond.amount;
+    info.sub_lamports(bond)?;
+    resolver_bond.amount = 0;
+    resolver_bond.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    Ok(bond)
+}
+
+/// Pays one `(betting_pool, match_record, user_bet)` triple of claim_many, owned by
+/// `better_key`, and returns the payout for the caller to credit.
+fn claim_triple<'info>(
+    triple: &'info [AccountInfo<'info>],
+    better_key: Pubkey,
+    house_vault: Option<&mut Account<'info, HouseVault>>,
+) -> Result<u64> {
+    let (pool_info, record_info, user_bet_info) = (&triple[0], &triple[1], &triple[2]);
+    require!(
+        pool_info.is_writable && user_bet_info.is_writable,
+        RpsError::InvalidClaimAccounts
+    );
+
+    // Same checks as the ClaimWinnings constraints, done by hand.
+    let mut user_bet = Account::<UserBet>::try_from(user_bet_info)?;
+    verify_pda(
+        user_bet_info,
+        &[
+            b"user_bet".as_ref(),
+            user_bet.origin.as_ref(),
+            &user_bet.match_id.to_le_bytes(),
+        ],
+        user_bet.bump,
+    )?;
+    require!(
+        user_bet.better == better_key,
+        RpsError::UserBetOwnerMismatch
+    );
+    user_bet.require_claimable()?;
+
+    let mut betting_pool = Account::<BettingPool>::try_from(pool_info)?;
+    verify_pda(
+        pool_info,
+        &[b"betting_pool".as_ref(), &user_bet.match_id.to_le_bytes()],
+        betting_pool.bump,
+    )?;
+    require!(
+        user_bet.match_id == betting_pool.match_id,
+        RpsError::MatchIdMismatch
+    );
+
+    let match_record = if record_info.key() == crate::ID {
+        None
+    } else {
+        let match_record = Account::<MatchRecord>::try_from(record_info)?;
+        verify_pda(
+            record_info,
+            &[b"match_record".as_ref(), &user_bet.match_id.to_le_bytes()],
+            match_record.bump,
+        )?;
+        Some(match_record)
+    };
+
+    let claim = compute_claim(
+        &betting_pool,
+        match_record.as_deref(),
+        user_bet.prediction,
+        user_bet.amount,
+        user_bet.locked_odds_bps,
+    )?;
+    debit_claim(&mut betting_pool, house_vault, &claim)?;
+    user_bet.claimed = true;
+    emit!(ClaimPaid::new(
+        user_bet.match_id,
+        user_bet_info.key(),
+        better_key,
+        &claim
+    ));
+
+    // Persist now so a later triple touching the same pool or bet sees the update.
+    betting_pool.exit(&crate::ID)?;
+    user_bet.exit(&crate::ID)?;
+    Ok(claim.payout)
+}
+
+/// Checks that `info` is the program address for `seeds` and `bump`.
+fn verify_pda(info: &AccountInfo, seeds: &[&[u8]], bump: u8) -> Result<()> {
+    let bump_seed = [bump];
+    let mut seeds_with_bump = seeds.to_vec();
+    seeds_with_bump.push(&bump_seed);
+    let expected = Pubkey::create_program_address(&seeds_with_bump, &crate::ID)
+        .map_err(|_| RpsError::InvalidClaimAccounts)?;
+    require_keys_eq!(info.key(), expected, RpsError::InvalidClaimAccounts);
+    Ok(())
+}
+
+/// Decodes a version 0 account. Only this program can create accounts it owns with a
+/// given discriminator, so owner, discriminator and old length identify it; PDA seeds
+/// need no separate check.
+fn load_legacy_account<T: AnchorDeserialize>(
+    info: &AccountInfo,
+    discriminator: &[u8],
+    legacy_len: usize,
+) -> Result<T> {
+    require_keys_eq!(*info.owner, crate::ID, RpsError::NotALegacyAccount);
+    let data = info.try_borrow_data()?;
+    require!(
+        data.len() == 8 + legacy_len && data.starts_with(discriminator),
+        RpsError::NotALegacyAccount
+    );
+    Ok(T::deserialize(&mut &data[8..])?)
+}
+
+/// Reads the bets in `infos` that were already claimed from match `match_id`. They may
+/// still be in the version 0 layout or already migrated. Addresses must be strictly
+/// ascending so that no bet is counted twice.
+fn load_claimed_legacy_bets(infos: &[AccountInfo], match_id: u64) -> Result<Vec<Bet>> {
+    require!(
+        infos.windows(2).all(|pair| pair[0].key < pair[1].key),
+        RpsError::InvalidMigrationAccounts
+    );
+    let mut claimed = Vec::with_capacity(infos.len());
+    for info in infos {
+        let (bet_match_id, bet, is_claimed) = if info.data_len() == 8 + UserBetV0::LEN {
+            let user_bet: UserBetV0 =
+                load_legacy_account(info, UserBet::DISCRIMINATOR, UserBetV0::LEN)?;
+            (
+                user_bet.match_id,
+                Bet {
+                    prediction: user_bet.prediction,
+                    amount: user_bet.amount,
+                },
+                user_bet.claimed,
+            )
+        } else {
+            require_keys_eq!(*info.owner, crate::ID, RpsError::InvalidMigrationAccounts);
+            let user_bet = UserBet::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            (
+                user_bet.match_id,
+                Bet {
+                    prediction: user_bet.prediction,
+                    amount: user_bet.amount,
+                },
+                user_bet.claimed,
+            )
+        };
+        require!(bet_match_id == match_id, RpsError::MatchIdMismatch);
+        if is_claimed {
+            claimed.push(bet);
+        }
+    }
+    Ok(claimed)
+}
+
+/// Grows `info` to `new_len`, topping up rent from `payer`, and writes `account` into it.
+fn write_migrated_account<'info, T: AccountSerialize>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    new_len: usize,
+    account: &T,
+) -> Result<()> {
+    let rent_due = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(info.lamports());
+    if rent_due > 0 {
+        let cpi_context = CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: payer.to_account_info(),
+                to: info.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, rent_due)?;
+    }
+    info.realloc(new_len, false)?;
+    let mut data = info.try_borrow_mut_data()?;
+    account.try_serialize(&mut &mut data[..])?;
+    Ok(())
+}
+
+/// LP shares minted to the depositor for `amount` lamports deposited into a vault worth
+/// `net_assets`. A deposit into an empty share supply must be at least
+/// `MIN_FIRST_DEPOSIT_LAMPORTS`; it is worth `amount - LOCKED_LP_SHARES` to the depositor,
+/// and whatever the vault already held stays behind the locked shares. Into an empty
+/// vault that mints 1:1.
+pub fn lp_shares_for_deposit(amount: u64, net_assets: u64, share_supply: u64) -> Result<u64> {
+    if share_supply == 0 {
+        require!(
+            amount >= MIN_FIRST_DEPOSIT_LAMPORTS,
+            RpsError::FirstDepositTooSmall
+        );
+        // Solves shares / (shares + LOCKED) * (net_assets + amount) = amount - LOCKED.
+        let shares = ((amount - LOCKED_LP_SHARES) as u128)
+            .checked_mul(LOCKED_LP_SHARES as u128)
+            .ok_or(RpsError::Overflow)?
+            / (net_assets as u128 + LOCKED_LP_SHARES as u128);
+        return Ok(u64::try_from(shares).map_err(|_| RpsError::Overflow)?);
+    }
+    // Outstanding shares with nothing backing them cannot be priced.
+    require!(net_assets > 0, RpsError::HouseVaultInsolvent);
+    let shares = (amount as u128)
+        .checked_mul(share_supply as u128)
+        .ok_or(RpsError::Overflow)?
+        / net_assets as u128;
+    Ok(u64::try_from(shares).map_err(|_| RpsError::Overflow)?)
+}
+
+/// Lamports redeemed by burning `shares` out of `share_supply` against `net_assets`.
+pub fn lp_shares_value(shares: u64, net_assets: u64, share_supply: u64) -> Result<u64> {
+    require!(share_supply > 0, RpsError::DivisionByZero);
+    let value = (shares as u128)
+        .checked_mul(net_assets as u128)
+        .ok_or(RpsError::Overflow)?
+        / share_supply as u128;
+    Ok(u64::try_from(value).map_err(|_| RpsError::Overflow)?)
+}
+
+/// Books the keeper reward for one distributed bet against the pool's uncollected fees
+/// and returns it. Pools without fees to spare, such as refunds and fixed-odds markets,
+/// pay no reward. The caller moves the lamports.
+fn take_keeper_reward(betting_pool: &mut BettingPool) -> Result<u64> {
+    let uncollected = betting_pool
+        .fees_accrued
+        .checked_sub(betting_pool.fees_collected)
+        .ok_or(RpsError::Overflow)?;
+    let reward = uncollected.min(KEEPER_REWARD_LAMPORTS);
+    betting_pool.fees_collected = betting_pool
+        .fees_collected
+        .checked_add(reward)
+        .ok_or(RpsError::Overflow)?;
+    Ok(reward)
+}
+
+/// Moves the fees a pool has accrued but not yet collected into the house vault.
+/// Returns the lamports moved.
+fn move_fees_to_vault(
+    betting_pool: &mut Account<BettingPool>,
+    house_vault: &mut Account<HouseVault>,
+) -> Result<u64> {
+    let uncollected = betting_pool
+        .fees_accrued
+        .checked_sub(betting_pool.fees_collected)
+        .ok_or(RpsError::Overflow)?;
+    betting_pool.sub_lamports(uncollected)?;
+    house_vault.add_lamports(uncollected)?;
+    betting_pool.fees_collected = betting_pool.fees_accrued;
+    house_vault.total_fees_collected = house_vault
+        .total_fees_collected
+        .checked_add(uncollected)
+        .ok_or(RpsError::Overflow)?;
+    Ok(uncollected)
+}
+
+/// Moves the house vault reservation held for `betting_pool` to `reserved` lamports.
+fn set_house_reservation(
+    betting_pool: &mut BettingPool,
+    house_vault: &mut HouseVault,
+    reserved: u64,
+) -> Result<()> {
+    house_vault.total_liability = house_vault
+        .total_liability
+        .checked_sub(betting_pool.house_reserved)
+        .and_then(|liability| liability.checked_add(reserved))
+        .ok_or(RpsError::Overflow)?;
+    betting_pool.house_reserved = reserved;
+    Ok(())
+}
+
+// --- ACCOUNTS CONTEXTS ---
+#[derive(Accounts)]
+pub struct InitializeGame<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GameState::INIT_SPACE,
+        seeds = [b"game_state".as_ref()],
+        bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury".as_ref()],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state".as_ref()],
+        bump = game_state.bump,
+        constraint = game_state.authority == authority.key() @ RpsError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(min_bet_threshold_lamports: u64, betting_deadline: Option<BettingTime>, betting_opens_at: Option<BettingTime>, bet_limits: BetLimits, imbalance_guard: Option<ImbalanceGuard>, market: MarketKind)]
+pub struct CreateMatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state".as_ref()],
+        bump = game_state.bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    #[account(
+        init,
+        payer = match_creator_signer,
+        space = 8 + BettingPool::INIT_SPACE,
+        seeds = [b"betting_pool".as_ref(), &game_state.next_match_id.to_le_bytes()],
+        bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        seeds = [b"betting_pool_authority".as_ref(), &game_state.next_match_id.to_le_bytes()],
+        bump
+    )]
+    /// CHECK: PDA authority, no data stored.
+    pub betting_pool_authority: AccountInfo<'info>,
+    #[account(
+        init,
+        payer = match_creator_signer,
+        space = 8 + ResolverBond::INIT_SPACE,
+        seeds = [b"resolver_bond".as_ref(), &game_state.next_match_id.to_le_bytes()],
+        bump
+    )]
+    pub resolver_bond: Account<'info, ResolverBond>,
+    #[account(mut)]
+    pub match_creator_signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenBetting<'info> {
+    #[account(
+        mut,
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+}
+
+#[derive(Accounts)]
+// The prediction is read as its raw byte so place_bet and place_bet_legacy can share
+// this context; Prediction is Borsh-encoded as the same single byte.
+#[instruction(amount: u64, prediction_raw: u8)]
+pub struct PlaceBet<'info> {
+    #[account(
+        mut, // betting_pool is mutable here because its fields total_ai1_bets etc. are updated AFTER CPI
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        init_if_needed, // repeat bets on the same side top up the existing UserBet
+        payer = better,
+        space = 8 + UserBet::INIT_SPACE,
+        seeds = [
+            b"user_bet".as_ref(),
+            better.key().as_ref(),
+            &betting_pool.match_id.to_le_bytes() // This read from betting_pool is fine
+        ],
+        bump
+    )]
+    pub user_bet: Account<'info, UserBet>,
+    #[account(
+        mut, // required for fixed-odds markets, which stake into the vault
+        seeds = [b"house_vault".as_ref()],
+        bump = house_vault.bump
+    )]
+    pub house_vault: Option<Account<'info, HouseVault>>,
+    #[account(
+        mut, // provided to receive position tokens instead of a UserBet credit
+        seeds = [b"position_mint".as_ref(), &betting_pool.match_id.to_le_bytes(), &[prediction_raw]],
+        bump
+    )]
+    pub position_mint: Option<Account<'info, Mint>>,
+    #[account(mut)]
+    pub position_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"betting_pool_authority".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.pool_authority_bump
+    )]
+    /// CHECK: PDA authority, no data stored. Signs position token mints.
+    pub betting_pool_authority: Option<AccountInfo<'info>>,
+    pub token_program: Option<Program<'info, Token>>,
+    #[account(mut)]
+    pub better: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CheckBettingDeadline<'info> {
+    #[account(
+        mut,
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveMatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state".as_ref()],
+        bump = game_state.bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    #[account(
+        mut, // betting_pool is mutable for status update
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump,
+        constraint = betting_pool.authority == resolver_signer.key() @ RpsError::Unauthorized
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        init,
+        payer = resolver_signer,
+        space = 8 + MatchRecord::INIT_SPACE,
+        seeds = [b"match_record".as_ref(), &betting_pool.match_id.to_le_bytes()], // Read from betting_pool fine
+        bump
+    )]
+    pub match_record: Account<'info, MatchRecord>,
+    #[account(mut)]
+    pub resolver_signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelUnresolvedMatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    /// CHECK: the match's ResolverBond, which pools migrated from version 0 while
+    /// awaiting resolution never had. Pinned by its seeds and loaded by take_resolver_bond.
+    #[account(
+        mut,
+        seeds = [b"resolver_bond".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump
+    )]
+    pub resolver_bond: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"treasury".as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseResolverBond<'info> {
+    #[account(
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        mut,
+        seeds = [b"resolver_bond".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = resolver_bond.bump,
+        has_one = resolver,
+        close = resolver
+    )]
+    pub resolver_bond: Account<'info, ResolverBond>,
+    #[account(mut)]
+    pub resolver: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeMatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+}
+
+#[derive(Accounts)]
+pub struct RaiseDispute<'info> {
+    #[account(
+        seeds = [b"game_state".as_ref()],
+        bump = game_state.bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    #[account(
+        mut, // betting_pool is mutable for status update
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        init,
+        payer = disputer,
+        space = 8 + Dispute::INIT_SPACE,
+        seeds = [b"dispute".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(mut)]
+    pub disputer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdjudicateDispute<'info> {
+    #[account(
+        seeds = [b"game_state".as_ref()],
+        bump = game_state.bump,
+        constraint = game_state.authority == authority.key() @ RpsError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
+    #[account(
+        mut, // betting_pool is mutable for status update
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        mut, // match_record is rewritten when the result is overturned
+        seeds = [b"match_record".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = match_record.bump
+    )]
+    pub match_record: Account<'info, MatchRecord>,
+    #[account(
+        mut,
+        seeds = [b"dispute".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = dispute.bump,
+        has_one = disputer,
+        close = disputer
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(mut)]
+    pub disputer: SystemAccount<'info>,
+    /// CHECK: the match's ResolverBond, absent for pools migrated from version 0 while
+    /// awaiting resolution. Pinned by its seeds and loaded by take_resolver_bond.
+    #[account(
+        mut,
+        seeds = [b"resolver_bond".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump
+    )]
+    pub resolver_bond: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"treasury".as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(
+        seeds = [b"game_state".as_ref()],
+        bump = game_state.bump,
+        constraint = game_state.authority == authority.key() @ RpsError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
+    #[account(
+        mut,
+        seeds = [b"treasury".as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub recipient: SystemAccount<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeHouseVault<'info> {
+    #[account(
+        seeds = [b"game_state".as_ref()],
+        bump = game_state.bump,
+        constraint = game_state.authority == authority.key() @ RpsError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + HouseVault::INIT_SPACE,
+        seeds = [b"house_vault".as_ref()],
+        bump
+    )]
+    pub house_vault: Account<'info, HouseVault>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"house_lp_mint".as_ref()],
+        bump,
+        mint::decimals = LP_MINT_DECIMALS,
+        mint::authority = house_vault
+    )]
+    pub lp_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"house_lp_escrow".as_ref()],
+        bump,
+        token::mint = lp_mint,
+        token::authority = house_vault
+    )]
+    pub lp_escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [b"house_vault".as_ref()],
+        bump = house_vault.bump,
+        has_one = lp_mint
+    )]
+    pub house_vault: Account<'info, HouseVault>,
+    #[account(mut)]
+    pub lp_mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        associated_token::mint = lp_mint,
+        associated_token::authority = depositor
+    )]
+    pub depositor_lp_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"house_lp_escrow".as_ref()],
+        bump
+    )]
+    pub lp_escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdrawal<'info> {
+    #[account(
+        seeds = [b"house_vault".as_ref()],
+        bump = house_vault.bump
+    )]
+    pub house_vault: Account<'info, HouseVault>,
+    #[account(
+        mut,
+        token::mint = house_vault.lp_mint,
+        token::authority = owner
+    )]
+    pub owner_lp_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"house_lp_escrow".as_ref()],
+        bump
+    )]
+    pub lp_escrow: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + LiquidityWithdrawal::INIT_SPACE,
+        seeds = [b"lp_withdrawal".as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub withdrawal: Account<'info, LiquidityWithdrawal>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelWithdrawal<'info> {
+    #[account(
+        seeds = [b"house_vault".as_ref()],
+        bump = house_vault.bump
+    )]
+    pub house_vault: Account<'info, HouseVault>,
+    #[account(
+        mut,
+        token::mint = house_vault.lp_mint,
+        token::authority = owner
+    )]
+    pub owner_lp_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"house_lp_escrow".as_ref()],
+        bump
+    )]
+    pub lp_escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"lp_withdrawal".as_ref(), owner.key().as_ref()],
+        bump = withdrawal.bump,
+        has_one = owner,
+        close = owner
+    )]
+    pub withdrawal: Account<'info, LiquidityWithdrawal>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [b"house_vault".as_ref()],
+        bump = house_vault.bump,
+        has_one = lp_mint
+    )]
+    pub house_vault: Account<'info, HouseVault>,
+    #[account(mut)]
+    pub lp_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"house_lp_escrow".as_ref()],
+        bump
+    )]
+    pub lp_escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"lp_withdrawal".as_ref(), owner.key().as_ref()],
+        bump = withdrawal.bump,
+        has_one = owner,
+        close = owner
+    )]
+    pub withdrawal: Account<'info, LiquidityWithdrawal>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CollectPoolFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        mut,
+        seeds = [b"house_vault".as_ref()],
+        bump = house_vault.bump
+    )]
+    pub house_vault: Account<'info, HouseVault>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseHouseLiability<'info> {
+    #[account(
+        mut,
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        seeds = [b"match_record".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = match_record.bump
+    )]
+    pub match_record: Option<Account<'info, MatchRecord>>, // absent for cancelled matches
+    #[account(
+        mut,
+        seeds = [b"house_vault".as_ref()],
+        bump = house_vault.bump
+    )]
+    pub house_vault: Account<'info, HouseVault>,
+}
+
+#[derive(Accounts)]
+pub struct EnablePositionTokens<'info> {
+    #[account(
+        mut,
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump,
+        constraint = betting_pool.authority == authority.key() @ RpsError::Unauthorized
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        seeds = [b"betting_pool_authority".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.pool_authority_bump
+    )]
+    /// CHECK: PDA authority, no data stored.
+    pub betting_pool_authority: AccountInfo<'info>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"position_mint".as_ref(), &betting_pool.match_id.to_le_bytes(), &[0u8]],
+        bump,
+        mint::decimals = POSITION_MINT_DECIMALS,
+        mint::authority = betting_pool_authority
+    )]
+    pub ai1_position_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"position_mint".as_ref(), &betting_pool.match_id.to_le_bytes(), &[1u8]],
+        bump,
+        mint::decimals = POSITION_MINT_DECIMALS,
+        mint::authority = betting_pool_authority
+    )]
+    pub ai2_position_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(side: Prediction)]
+pub struct RedeemPosition<'info> {
+    #[account(
+        mut, // betting_pool pays out the redemption
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        seeds = [b"match_record".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = match_record.bump
+    )]
+    pub match_record: Option<Account<'info, MatchRecord>>, // absent for cancelled matches
+    #[account(
+        mut,
+        seeds = [b"position_mint".as_ref(), &betting_pool.match_id.to_le_bytes(), &[side as u8]],
+        bump
+    )]
+    pub position_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = position_mint,
+        token::authority = holder
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ListBet<'info> {
+    #[account(
+        seeds = [b"betting_pool".as_ref(), &user_bet.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        seeds = [
+            b"user_bet".as_ref(),
+            user_bet.origin.as_ref(),
+            &user_bet.match_id.to_le_bytes()
+        ],
+        bump = user_bet.bump,
+        constraint = user_bet.better == seller.key() @ RpsError::UserBetOwnerMismatch
+    )]
+    pub user_bet: Account<'info, UserBet>,
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + BetListing::INIT_SPACE,
+        seeds = [b"bet_listing".as_ref(), user_bet.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, BetListing>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelListing<'info> {
+    #[account(
+        mut,
+        seeds = [b"bet_listing".as_ref(), listing.user_bet.as_ref()],
+        bump = listing.bump,
+        has_one = seller,
+        close = seller
+    )]
+    pub listing: Account<'info, BetListing>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BuyBet<'info> {
+    #[account(
+        seeds = [b"betting_pool".as_ref(), &user_bet.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        mut, // ownership is reassigned to the buyer
+        seeds = [
+            b"user_bet".as_ref(),
+            user_bet.origin.as_ref(),
+            &user_bet.match_id.to_le_bytes()
+        ],
+        bump = user_bet.bump,
+        constraint = user_bet.better == seller.key() @ RpsError::UserBetOwnerMismatch
+    )]
+    pub user_bet: Account<'info, UserBet>,
+    #[account(
+        mut,
+        seeds = [b"bet_listing".as_ref(), user_bet.key().as_ref()],
+        bump = listing.bump,
+        has_one = seller,
+        close = seller
+    )]
+    pub listing: Account<'info, BetListing>,
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+    #[account(
+        mut, // betting_pool is mutable for CPI (SOL transfer from it)
+        seeds = [b"betting_pool".as_ref(), &user_bet.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        seeds = [b"betting_pool_authority".as_ref(), &user_bet.match_id.to_le_bytes()],
+        bump = betting_pool.pool_authority_bump
+    )]
+    /// CHECK: PDA authority, no data stored.
+    pub betting_pool_authority: AccountInfo<'info>,
+    #[account(
+        seeds = [b"match_record".as_ref(), &user_bet.match_id.to_le_bytes()],
+        bump = match_record.bump
+    )]
+    pub match_record: Option<Account<'info, MatchRecord>>, // absent for cancelled matches
+    #[account(
+        mut, // required for fixed-odds markets, which pay out of the vault
+        seeds = [b"house_vault".as_ref()],
+        bump = house_vault.bump
+    )]
+    pub house_vault: Option<Account<'info, HouseVault>>,
+    #[account(
+        mut, // user_bet is mutable for `claimed` field update
+        seeds = [
+            b"user_bet".as_ref(),
+            user_bet.origin.as_ref(), // the original bettor; ownership may have been sold since
+            &user_bet.match_id.to_le_bytes()
+        ],
+        bump = user_bet.bump,
+        constraint = user_bet.better == better.key() @ RpsError::UserBetOwnerMismatch
+    )]
+    pub user_bet: Account<'info, UserBet>,
+    #[account(mut)]
+    pub better: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimMany<'info> {
+    #[account(
+        mut, // required when any of the claimed bets is on a fixed-odds market
+        seeds = [b"house_vault".as_ref()],
+        bump = house_vault.bump
+    )]
+    pub house_vault: Option<Account<'info, HouseVault>>,
+    #[account(mut)]
+    pub better: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Distribute<'info> {
+    #[account(
+        mut,
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        seeds = [b"match_record".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = match_record.bump
+    )]
+    pub match_record: Option<Account<'info, MatchRecord>>, // absent for cancelled matches
+    #[account(
+        mut, // required for fixed-odds markets, which pay out of the vault
+        seeds = [b"house_vault".as_ref()],
+        bump = house_vault.bump
+    )]
+    pub house_vault: Option<Account<'info, HouseVault>>,
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SweepUnclaimed<'info> {
+    #[account(
+        mut,
+        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
+        bump = betting_pool.bump,
+        close = treasury
+    )]
+    pub betting_pool: Account<'info, BettingPool>,
+    #[account(
+        mut, // required for fixed-odds markets and pools with uncollected fees
+        seeds = [b"house_vault".as_ref()],
+        bump = house_vault.bump
+    )]
+    pub house_vault: Option<Account<'info, HouseVault>>,
+    #[account(
+        mut,
+        seeds = [b"treasury".as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateGameState<'info> {
+    #[account(mut, seeds = [b"game_state".as_ref()], bump)]
+    /// CHECK: Still in the version 0 layout; decoded by hand in migrate_game_state.
+    pub game_state: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury".as_ref()],
         bump
     )]
-    pub user_bet: Account<'info, UserBet>,
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateBettingPool<'info> {
+    #[account(mut)]
+    /// CHECK: Still in the version 0 layout; decoded by hand in migrate_betting_pool.
+    pub betting_pool: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"game_state".as_ref()],
+        bump = game_state.bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    /// Only needed to carry over claims from a settled pool.
+    pub match_record: Option<Account<'info, MatchRecord>>,
     #[account(mut)]
-    pub better: Signer<'info>,
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Shared by the migrations that need nothing but the old account.
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    #[account(mut)]
+    /// CHECK: Still in the version 0 layout; decoded by hand in the migrate_* handler.
+    pub account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
-#[derive(Accounts)]
-#[instruction(match_id_arg: u64)]
-pub struct CheckBettingDeadline<'info> {
-    #[account(
-        mut,
-        seeds = [b"betting_pool".as_ref(), &match_id_arg.to_le_bytes()],
-        bump = betting_pool.bump
-    )]
-    pub betting_pool: Account<'info, BettingPool>,
+// --- DATA STRUCTURES (ACCOUNTS & ENUMS) ---
+// ... (These should be correct from the previous full version) ...
+#[account]
+#[derive(Default, InitSpace)]
+pub struct GameState {
+    pub version: u8,
+    pub authority: Pubkey,
+    pub next_match_id: u64,
+    pub total_matches: u64,
+    pub bump: u8,
+    pub dispute_window_slots: u64,
+    pub dispute_bond_lamports: u64,
+    pub resolver_bond_lamports: u64,
+    pub resolution_timeout_slots: u64,
+    pub platform_fee_bps: u64,
+    pub claim_window_slots: u64,
+    // Taken from the reserved space, so version 1 accounts read them as 0 (unbounded).
+    pub default_betting_duration_slots: u64,
+    pub min_betting_duration_slots: u64,
+    pub max_betting_duration_slots: u64,
+    pub min_bet_threshold_floor: u64,
+    pub min_bet_threshold_ceiling: u64,
+    pub reserved: [u64; 11], // zeroed space for future fields
+}
+impl GameState {
+    pub fn config(&self) -> GameConfig {
+        GameConfig {
+            default_betting_duration_slots: self.default_betting_duration_slots,
+            min_betting_duration_slots: self.min_betting_duration_slots,
+            max_betting_duration_slots: self.max_betting_duration_slots,
+            min_bet_threshold_floor: self.min_bet_threshold_floor,
+            min_bet_threshold_ceiling: self.min_bet_threshold_ceiling,
+            platform_fee_bps: self.platform_fee_bps,
+            resolution_timeout_slots: self.resolution_timeout_slots,
+            dispute_window_slots: self.dispute_window_slots,
+            dispute_bond_lamports: self.dispute_bond_lamports,
+            resolver_bond_lamports: self.resolver_bond_lamports,
+            claim_window_slots: self.claim_window_slots,
+        }
+    }
+
+    fn apply_config(&mut self, config: &GameConfig) {
+        self.default_betting_duration_slots = config.default_betting_duration_slots;
+        self.min_betting_duration_slots = config.min_betting_duration_slots;
+        self.max_betting_duration_slots = config.max_betting_duration_slots;
+        self.min_bet_threshold_floor = config.min_bet_threshold_floor;
+        self.min_bet_threshold_ceiling = config.min_bet_threshold_ceiling;
+        self.platform_fee_bps = config.platform_fee_bps;
+        self.resolution_timeout_slots = config.resolution_timeout_slots;
+        self.dispute_window_slots = config.dispute_window_slots;
+        self.dispute_bond_lamports = config.dispute_bond_lamports;
+        self.resolver_bond_lamports = config.resolver_bond_lamports;
+        self.claim_window_slots = config.claim_window_slots;
+    }
+}
+
+/// Game-wide parameters set by the authority. A maximum or ceiling of 0 means unbounded.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct GameConfig {
+    /// Betting window used when create_match is given no deadline. 0 makes it required.
+    pub default_betting_duration_slots: u64,
+    pub min_betting_duration_slots: u64,
+    pub max_betting_duration_slots: u64,
+    pub min_bet_threshold_floor: u64,
+    pub min_bet_threshold_ceiling: u64,
+    pub platform_fee_bps: u64,
+    pub resolution_timeout_slots: u64,
+    pub dispute_window_slots: u64,
+    pub dispute_bond_lamports: u64,
+    pub resolver_bond_lamports: u64,
+    pub claim_window_slots: u64,
+}
+impl GameConfig {
+    fn validate(&self) -> Result<()> {
+        require!(self.claim_window_slots > 0, RpsError::InvalidClaimWindow);
+        // Without a window, finalize_match could run in the resolving slot and no
+        // dispute could ever be raised.
+        require!(self.dispute_window_slots > 0, RpsError::InvalidGameConfig);
+        // A zero timeout would let anyone cancel and slash the resolver's bond in the
+        // slot betting closes, before the resolver could act.
+        require!(
+            self.resolution_timeout_slots > 0,
+            RpsError::InvalidGameConfig
+        );
+        require!(
+            self.platform_fee_bps <= MAX_PLATFORM_FEE_BPS,
+            RpsError::InvalidPlatformFee
+        );
+        // A zero-length betting window can never take a bet.
+        require!(
+            self.min_betting_duration_slots > 0,
+            RpsError::InvalidGameConfig
+        );
+        if self.max_betting_duration_slots > 0 {
+            require!(
+                self.min_betting_duration_slots <= self.max_betting_duration_slots,
+                RpsError::InvalidGameConfig
+            );
+        }
+        if self.default_betting_duration_slots > 0 {
+            require!(
+                self.default_betting_duration_slots >= self.min_betting_duration_slots
+                    && (self.max_betting_duration_slots == 0
+                        || self.default_betting_duration_slots <= self.max_betting_duration_slots),
+                RpsError::InvalidGameConfig
+            );
+        }
+        if self.min_bet_threshold_ceiling > 0 {
+            require!(
+                self.min_bet_threshold_floor <= self.min_bet_threshold_ceiling,
+                RpsError::InvalidGameConfig
+            );
+        }
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Treasury {
+    pub total_collected: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct BettingPool {
+    pub version: u8,
+    pub authority: Pubkey,
+    pub match_id: u64,
+    pub total_ai1_bets: u64,
+    pub total_ai2_bets: u64,
+    pub status: MatchStatus,
+    pub bump: u8,
+    pub pool_authority_bump: u8,
+    pub betting_deadline: BettingTime,
+    pub min_bet_threshold: u64,
+    pub challenge_deadline_slot: u64,
+    pub resolution_timeout_slots: u64,
+    pub resolution_deadline_slot: u64,
+    pub slashed_bond: u64,
+    pub betting_opens_at: BettingTime,
+    pub bet_limits: BetLimits,
+    pub imbalance_guard: Option<ImbalanceGuard>,
+    pub market: MarketKind,
+    pub ai1_house_liability: u64,
+    pub ai2_house_liability: u64,
+    pub house_reserved: u64,
+    pub house_liability_settled: bool,
+    pub fee_bps: u64,
+    pub fees_accrued: u64,
+    pub fees_collected: u64,
+    pub position_tokens_enabled: bool,
+    pub claim_window_slots: u64,
+    pub claim_deadline_slot: u64, // 0 until the pool becomes claimable
+    pub total_paid_out: u64,
+    pub claimed_count: u64,
+    pub claimed_stake: u64,  // stake returned so far, excluding losing bets
+    pub reserved: [u64; 16], // zeroed space for future fields
+}
+impl BettingPool {
+    /// Starts the claim window; called when the pool becomes claimable or refundable.
+    fn open_claim_window(&mut self, slot: u64) -> Result<()> {
+        self.claim_deadline_slot = slot
+            .checked_add(self.claim_window_slots)
+            .ok_or(RpsError::Overflow)?;
+        Ok(())
+    }
+
+    fn require_claim_window_open(&self, slot: u64) -> Result<()> {
+        require!(slot < self.claim_deadline_slot, RpsError::ClaimWindowClosed);
+        Ok(())
+    }
+
+    /// A pool can be swept once its claim window has opened and closed again.
+    fn require_sweepable(&self, slot: u64) -> Result<()> {
+        require!(
+            self.claim_deadline_slot > 0,
+            RpsError::MatchNotReadyForClaimOrRefund
+        );
+        require!(
+            slot >= self.claim_deadline_slot,
+            RpsError::ClaimWindowStillOpen
+        );
+        Ok(())
+    }
+
+    /// Books a claim computed by `compute_claim` against the pool's running totals.
+    fn record_claim(&mut self, claim: &ClaimAmounts) -> Result<()> {
+        self.fees_accrued = self
+            .fees_accrued
+            .checked_add(claim.fee)
+            .ok_or(RpsError::Overflow)?;
+        self.total_paid_out = self
+            .total_paid_out
+            .checked_add(claim.payout)
+            .ok_or(RpsError::Overflow)?;
+        self.claimed_stake = self
+            .claimed_stake
+            .checked_add(claim.stake)
+            .ok_or(RpsError::Overflow)?;
+        self.claimed_count = self
+            .claimed_count
+            .checked_add(1)
+            .ok_or(RpsError::Overflow)?;
+
+        // A parimutuel pool can never hand out more than was put into it.
+        if !self.market.is_fixed_odds() {
+            let deposits = self
+                .total_bets()?
+                .checked_add(self.slashed_bond)
+                .ok_or(RpsError::Overflow)?;
+            let paid = self
+                .total_paid_out
+                .checked_add(self.fees_accrued)
+                .ok_or(RpsError::Overflow)?;
+            require!(paid <= deposits, RpsError::PayoutExceedsDeposits);
+        }
+        Ok(())
+    }
+
+    /// Whether claiming `amount` more stake returns the last of `claimable_stake`.
+    fn is_last_claim(&self, amount: u64, claimable_stake: u64) -> Result<bool> {
+        let claimed = self
+            .claimed_stake
+            .checked_add(amount)
+            .ok_or(RpsError::Overflow)?;
+        Ok(claimed == claimable_stake)
+    }
+
+    fn total_bets(&self) -> Result<u64> {
+        Ok(self
+            .total_ai1_bets
+            .checked_add(self.total_ai2_bets)
+            .ok_or(RpsError::Overflow)?)
+    }
+
+    /// Adds what `amount` more at `odds_bps` on `prediction` could cost the house, for a
+    /// bettor who already had `previous_amount` there. It is computed on the bettor's
+    /// running total so the sum matches what claim_winnings will pay out.
+    fn add_house_liability(
+        &mut self,
+        prediction: Prediction,
+        previous_amount: u64,
+        amount: u64,
+        odds_bps: u64,
+    ) -> Result<()> {
+        let user_total = previous_amount
+            .checked_add(amount)
+            .ok_or(RpsError::Overflow)?;
+        let added_liability = fixed_odds_payout(user_total, odds_bps)?
+            .checked_sub(fixed_odds_payout(previous_amount, odds_bps)?)
+            .ok_or(RpsError::Overflow)?;
+        let side_liability = match prediction {
+            Prediction::Ai1 => &mut self.ai1_house_liability,
+            Prediction::Ai2 => &mut self.ai2_house_liability,
+        };
+        *side_liability = side_liability
+            .checked_add(added_liability)
+            .ok_or(RpsError::Overflow)?;
+        Ok(())
+    }
+
+    /// The most a fixed-odds pool can cost the house: either side winning, or a draw
+    /// refunding every stake.
+    fn worst_case_house_liability(&self) -> Result<u64> {
+        Ok(self
+            .ai1_house_liability
+            .max(self.ai2_house_liability)
+            .max(self.total_bets()?))
+    }
+
+    /// What the house owes once the result is final. `None` means the match was cancelled.
+    fn final_house_liability(&self, outcome: Option<Winner>) -> Result<u64> {
+        match outcome {
+            Some(Winner::Ai1) => Ok(self.ai1_house_liability),
+            Some(Winner::Ai2) => Ok(self.ai2_house_liability),
+            Some(Winner::Draw) | None => self.total_bets(),
+        }
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct MatchRecord {
+    pub version: u8,
+    pub match_id: u64,
+    pub timestamp: i64,
+    pub ai1_move: Move,
+    pub ai2_move: Move,
+    pub winner: Winner,
+    pub total_bet_amount: u64,
+    pub bump: u8,
+    pub overturned: bool,
+    pub reserved: [u64; 4], // zeroed space for future fields
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Dispute {
+    pub match_id: u64,
+    pub disputer: Pubkey,
+    pub bond: u64,
+    pub raised_slot: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct ResolverBond {
+    pub match_id: u64,
+    pub resolver: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct UserBet {
+    pub version: u8,
+    pub better: Pubkey,
+    pub match_id: u64,
+    pub prediction: Prediction,
+    pub amount: u64,
+    pub claimed: bool,
+    pub bump: u8,
+    pub locked_odds_bps: u64,
+    pub tokenized_amount: u64,
+    /// The bettor who placed the bet. Used for the PDA seeds, which must not change
+    /// when the bet is sold to a new `better`.
+    pub origin: Pubkey,
+    pub reserved: [u64; 4], // zeroed space for future fields
+}
+impl UserBet {
+    /// Checks that the bet still holds an unclaimed stake. A bet placed only through
+    /// position tokens keeps a UserBet with nothing staked on it, which must not count
+    /// as a claim.
+    fn require_claimable(&self) -> Result<()> {
+        require!(!self.claimed, RpsError::AlreadyClaimed);
+        require!(self.amount > 0, RpsError::NothingToClaim);
+        Ok(())
+    }
+
+    /// Bets can change hands until the result is published, as long as there is an
+    /// unclaimed stake on them.
+    fn require_tradable(&self, status: MatchStatus) -> Result<()> {
+        require!(
+            matches!(
+                status,
+                MatchStatus::OpenForBetting | MatchStatus::AwaitingResolution
+            ),
+            RpsError::BetNotTradable
+        );
+        require!(!self.claimed, RpsError::AlreadyClaimed);
+        require!(self.amount > 0, RpsError::BetAmountZero);
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct BetListing {
+    pub user_bet: Pubkey,
+    pub seller: Pubkey,
+    pub price_lamports: u64,
+    pub listed_amount: u64,
+    pub bump: u8,
+}
+impl BetListing {
+    /// Checks that `buyer` may take `user_bet` as listed. The seller may have topped the
+    /// bet up since listing it; the buyer only agreed to the listed position.
+    fn require_buyable(&self, user_bet: &UserBet, buyer: &Pubkey) -> Result<()> {
+        require!(
+            user_bet.amount == self.listed_amount,
+            RpsError::BetChangedSinceListing
+        );
+        require!(*buyer != self.seller, RpsError::CannotBuyOwnBet);
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct HouseVault {
+    pub total_liability: u64,
+    pub bump: u8,
+    pub lp_mint: Pubkey,
+    pub withdrawal_cooldown_slots: u64,
+    pub total_fees_collected: u64,
+}
+impl HouseVault {
+    /// Lamports backing the LP shares: everything above rent that is not reserved for
+    /// outstanding fixed-odds liabilities.
+    fn net_assets(&self, vault_info: &AccountInfo) -> Result<u64> {
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(vault_info.data_len());
+        Ok(vault_info
+            .lamports()
+            .saturating_sub(rent_exempt_minimum)
+            .saturating_sub(self.total_liability))
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct LiquidityWithdrawal {
+    pub owner: Pubkey,
+    pub shares: u64,
+    pub unlock_slot: u64,
+    pub bump: u8,
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace,
+)]
+pub enum MatchStatus {
+    #[default]
+    OpenForBetting,
+    AwaitingResolution,
+    Settled,
+    CancelledDueToLowBets,
+    Resolved,
+    Disputed,
+    CancelledResolverNoShow,
+    Scheduled,
+    CancelledDueToImbalance,
+}
+
+/// A point in time measured either in slots or in Unix seconds from the `Clock` sysvar.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum BettingTime {
+    Slot(u64),
+    UnixTimestamp(i64),
+}
+impl Default for BettingTime {
+    fn default() -> Self {
+        BettingTime::Slot(0)
+    }
+}
+impl BettingTime {
+    pub fn has_passed(&self, clock: &Clock) -> bool {
+        match *self {
+            BettingTime::Slot(slot) => clock.slot >= slot,
+            BettingTime::UnixTimestamp(timestamp) => clock.unix_timestamp >= timestamp,
+        }
+    }
+
+    /// Slot at which `self` falls, estimating timestamps at APPROX_SLOT_DURATION_MS.
+    fn approx_slot(&self, clock: &Clock) -> u64 {
+        match *self {
+            BettingTime::Slot(slot) => slot,
+            BettingTime::UnixTimestamp(timestamp) => {
+                let slots = timestamp
+                    .saturating_sub(clock.unix_timestamp)
+                    .saturating_mul(1_000)
+                    / APPROX_SLOT_DURATION_MS as i64;
+                clock.slot.saturating_add_signed(slots)
+            }
+        }
+    }
+
+    /// True when `self` is at or before `other`. Mixed kinds are compared through
+    /// `approx_slot`.
+    fn is_before(&self, other: &BettingTime, clock: &Clock) -> bool {
+        match (*self, *other) {
+            (BettingTime::Slot(a), BettingTime::Slot(b)) => a <= b,
+            (BettingTime::UnixTimestamp(a), BettingTime::UnixTimestamp(b)) => a <= b,
+            _ => self.approx_slot(clock) <= other.approx_slot(clock),
+        }
+    }
+}
+
+/// Optional per-match caps on bet sizes, all in lamports. `None` leaves that dimension uncapped.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace,
+)]
+pub struct BetLimits {
+    pub min_bet: Option<u64>,
+    pub max_bet: Option<u64>,
+    /// Checked on each bettor's own UserBet in place_bet. Bets bought through buy_bet
+    /// are not added to it.
+    pub max_per_user: Option<u64>,
+    pub max_pool_size: Option<u64>,
+}
+impl BetLimits {
+    fn validate(&self) -> Result<()> {
+        if let (Some(min_bet), Some(max_bet)) = (self.min_bet, self.max_bet) {
+            require!(min_bet <= max_bet, RpsError::InvalidBetLimits);
+        }
+        if let (Some(min_bet), Some(max_per_user)) = (self.min_bet, self.max_per_user) {
+            require!(min_bet <= max_per_user, RpsError::InvalidBetLimits);
+        }
+        if let (Some(min_bet), Some(max_pool_size)) = (self.min_bet, self.max_pool_size) {
+            require!(min_bet <= max_pool_size, RpsError::InvalidBetLimits);
+        }
+        Ok(())
+    }
+
+    /// Checks a bet of `amount` on top of the bettor's `previous_total` and the pool's
+    /// `pool_total`.
+    fn check(&self, amount: u64, previous_total: u64, pool_total: u64) -> Result<()> {
+        if let Some(min_bet) = self.min_bet {
+            require!(amount >= min_bet, RpsError::BetBelowMinimum);
+        }
+        if let Some(max_bet) = self.max_bet {
+            require!(amount <= max_bet, RpsError::BetAboveMaximum);
+        }
+        if let Some(max_per_user) = self.max_per_user {
+            let user_total = previous_total
+                .checked_add(amount)
+                .ok_or(RpsError::Overflow)?;
+            require!(user_total <= max_per_user, RpsError::UserBetLimitExceeded);
+        }
+        if let Some(max_pool_size) = self.max_pool_size {
+            let pool_total = pool_total.checked_add(amount).ok_or(RpsError::Overflow)?;
+            require!(pool_total <= max_pool_size, RpsError::PoolSizeLimitExceeded);
+        }
+        Ok(())
+    }
+}
+
+/// Cancels a pool at the deadline when the larger side exceeds the smaller one by more
+/// than `max_side_ratio_bps` (10_000 = 1:1). A side with no bets always exceeds it.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace,
+)]
+pub struct ImbalanceGuard {
+    pub max_side_ratio_bps: u64,
+}
+impl ImbalanceGuard {
+    fn validate(&self) -> Result<()> {
+        require!(
+            self.max_side_ratio_bps >= BPS_DENOMINATOR,
+            RpsError::InvalidImbalanceGuard
+        );
+        Ok(())
+    }
+
+    fn is_exceeded(&self, total_ai1_bets: u64, total_ai2_bets: u64) -> bool {
+        let larger = total_ai1_bets.max(total_ai2_bets) as u128;
+        let smaller = total_ai1_bets.min(total_ai2_bets) as u128;
+        if smaller == 0 {
+            return true;
+        }
+        larger * BPS_DENOMINATOR as u128 > smaller * self.max_side_ratio_bps as u128
+    }
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace,
+)]
+pub enum MarketKind {
+    #[default]
+    Parimutuel,
+    /// Decimal odds per side in basis points, e.g. 25_000 pays 2.5x the stake.
+    FixedOdds {
+        ai1_odds_bps: u64,
+        ai2_odds_bps: u64,
+    },
+}
+impl MarketKind {
+    fn validate(&self) -> Result<()> {
+        if let MarketKind::FixedOdds {
+            ai1_odds_bps,
+            ai2_odds_bps,
+        } = *self
+        {
+            require!(
+                ai1_odds_bps > BPS_DENOMINATOR && ai2_odds_bps > BPS_DENOMINATOR,
+                RpsError::InvalidOdds
+            );
+        }
+        Ok(())
+    }
+
+    pub fn is_fixed_odds(&self) -> bool {
+        matches!(self, MarketKind::FixedOdds { .. })
+    }
+
+    pub fn odds_for(&self, prediction: Prediction) -> Option<u64> {
+        match (*self, prediction) {
+            (MarketKind::Parimutuel, _) => None,
+            (MarketKind::FixedOdds { ai1_odds_bps, .. }, Prediction::Ai1) => Some(ai1_odds_bps),
+            (MarketKind::FixedOdds { ai2_odds_bps, .. }, Prediction::Ai2) => Some(ai2_odds_bps),
+        }
+    }
+}
+
+/// The moves an overturned dispute replaces the published result with.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CorrectedMoves {
+    pub ai1_move: Move,
+    pub ai2_move: Move,
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace,
+)]
+pub enum Move {
+    #[default]
+    Rock,
+    Paper,
+    Scissors,
+}
+impl Move {
+    fn from_u8(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Move::Rock),
+            1 => Ok(Move::Paper),
+            2 => Ok(Move::Scissors),
+            _ => Err(RpsError::InvalidMoveValue.into()),
+        }
+    }
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace,
+)]
+pub enum Winner {
+    #[default]
+    Ai1,
+    Ai2,
+    Draw,
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace,
+)]
+pub enum Prediction {
+    #[default]
+    Ai1,
+    Ai2,
+}
+impl Prediction {
+    fn from_u8(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Prediction::Ai1),
+            1 => Ok(Prediction::Ai2),
+            _ => Err(RpsError::InvalidPrediction.into()),
+        }
+    }
 }
 
-#[derive(Accounts)]
-#[instruction(ai1_move_raw: u8, ai2_move_raw: u8)]
-pub struct ResolveMatch<'info> {
-    #[account(
-        mut,
-        seeds = [b"game_state".as_ref()],
-        bump = game_state.bump
-    )]
-    pub game_state: Account<'info, GameState>,
-    #[account(
-        mut, // betting_pool is mutable for status update
-        seeds = [b"betting_pool".as_ref(), &betting_pool.match_id.to_le_bytes()],
-        bump = betting_pool.bump,
-        constraint = betting_pool.authority == resolver_signer.key() @ RpsError::Unauthorized
-    )]
-    pub betting_pool: Account<'info, BettingPool>,
-    #[account(
-        init,
-        payer = resolver_signer,
-        space = 8 + MatchRecord::LEN,
-        seeds = [b"match_record".as_ref(), &betting_pool.match_id.to_le_bytes()], // Read from betting_pool fine
-        bump
+#[error_code]
+pub enum RpsError {
+    #[msg("A numeric operation caused an overflow.")]
+    Overflow,
+    #[msg("Action unauthorized. Signer does not have required permissions.")]
+    Unauthorized,
+    #[msg("Betting is closed or not yet open for this match.")]
+    BettingClosedOrNotOpen,
+    #[msg("Betting deadline for this match has already passed.")]
+    BettingDeadlinePassed,
+    #[msg("Bet amount must be greater than zero.")]
+    BetAmountZero,
+    #[msg("Invalid prediction value (must be 0 for AI1 or 1 for AI2).")]
+    InvalidPrediction,
+    #[msg("The provided match_id does not match the account's match_id.")]
+    MatchIdMismatch,
+    #[msg("The winner for this match has not been set yet (internal error).")]
+    WinnerNotSet,
+    #[msg("This bet has already been claimed.")]
+    AlreadyClaimed,
+    #[msg("Match is not in a state where claims or refunds are allowed.")]
+    MatchNotReadyForClaimOrRefund,
+    #[msg("No bets were placed on the winning outcome, or division by zero would occur.")]
+    NoWinningBets,
+    #[msg("The signer does not own this bet receipt.")]
+    UserBetOwnerMismatch,
+    #[msg("Division by zero occurred during payout calculation.")]
+    DivisionByZero,
+    #[msg("Invalid move value (must be 0 for Rock, 1 for Paper, 2 for Scissors).")]
+    InvalidMoveValue,
+    #[msg("Betting deadline for this match has not been reached yet.")]
+    BettingDeadlineNotReached,
+    #[msg("This match is not open for betting or has already been processed by deadline check.")]
+    MatchNotOpenOrAlreadyProcessed,
+    #[msg("Match is not in AwaitingResolution state, cannot resolve with AI moves.")]
+    MatchNotAwaitingResolution,
+    #[msg("Match record's ID does not match the betting pool's ID during claim.")]
+    MatchIdMismatchInRecord,
+    #[msg("Match is not in Resolved state.")]
+    MatchNotResolved,
+    #[msg("The challenge window for this match is still open.")]
+    ChallengeWindowOpen,
+    #[msg("The challenge window for this match has closed.")]
+    ChallengeWindowClosed,
+    #[msg("Match is not in Disputed state.")]
+    MatchNotDisputed,
+    #[msg("Treasury does not hold enough withdrawable lamports.")]
+    InsufficientTreasuryFunds,
+    #[msg("The resolution timeout for this match has not been reached yet.")]
+    ResolutionTimeoutNotReached,
+    #[msg("The resolver bond cannot be released until the match is settled or cancelled.")]
+    ResolverBondLocked,
+    #[msg("The betting deadline must be in the future.")]
+    InvalidBettingDeadline,
+    #[msg("Betting for this match has not opened yet.")]
+    BettingNotYetOpen,
+    #[msg("Match is not in Scheduled state.")]
+    MatchNotScheduled,
+    #[msg("The betting deadline must come after the betting open time.")]
+    InvalidBettingWindow,
+    #[msg("Bet amount is below this match's minimum bet.")]
+    BetBelowMinimum,
+    #[msg("Bet amount is above this match's maximum bet.")]
+    BetAboveMaximum,
+    #[msg("This bet would exceed the per-user limit for this match.")]
+    UserBetLimitExceeded,
+    #[msg("This bet would exceed the maximum pool size for this match.")]
+    PoolSizeLimitExceeded,
+    #[msg("Bet limits are inconsistent (minimum bet above a maximum).")]
+    InvalidBetLimits,
+    #[msg("Additional bets must back the same side as the existing bet.")]
+    PredictionMismatch,
+    #[msg("Imbalance guard ratio must be at least 10000 bps (1:1).")]
+    InvalidImbalanceGuard,
+    #[msg("Fixed odds must be greater than 10000 bps (1.0x) on both sides.")]
+    InvalidOdds,
+    #[msg("The house vault account is required for fixed-odds markets.")]
+    HouseVaultRequired,
+    #[msg("The house vault cannot cover the worst-case liability of this bet.")]
+    HouseCannotCoverLiability,
+    #[msg("This instruction only applies to fixed-odds markets.")]
+    NotFixedOddsMarket,
+    #[msg("The house liability for this match has already been settled.")]
+    HouseLiabilityAlreadySettled,
+    #[msg("The match record account is required for settled matches.")]
+    MatchRecordRequired,
+    #[msg("Platform fee exceeds the maximum of 1000 bps.")]
+    InvalidPlatformFee,
+    #[msg("Deposit amount is too small to mint any LP shares.")]
+    DepositAmountZero,
+    #[msg("Withdrawal must be for more than zero shares.")]
+    WithdrawalAmountZero,
+    #[msg("The withdrawal cooldown has not elapsed yet.")]
+    WithdrawalCooldownActive,
+    #[msg("The house vault has outstanding shares but no net assets.")]
+    HouseVaultInsolvent,
+    #[msg("This pool has no uncollected fees.")]
+    NoFeesToCollect,
+    #[msg("Position tokens are not enabled for this match.")]
+    PositionTokensNotEnabled,
+    #[msg("Position tokens are only available for parimutuel markets.")]
+    PositionTokensUnsupported,
+    #[msg(
+        "Tokenized bets need the position mint, token account, pool authority and token program."
     )]
-    pub match_record: Account<'info, MatchRecord>,
-    #[account(mut)]
-    pub resolver_signer: Signer<'info>,
-    pub system_program: Program<'info, System>,
+    PositionAccountsRequired,
+    #[msg("The position token account does not hold this match's position mint.")]
+    PositionMintMismatch,
+    #[msg("Redeem amount must be greater than zero.")]
+    RedeemAmountZero,
+    #[msg("Bets can only be traded while the match is open or awaiting resolution.")]
+    BetNotTradable,
+    #[msg("The bet has changed since it was listed.")]
+    BetChangedSinceListing,
+    #[msg("The seller cannot buy their own listing.")]
+    CannotBuyOwnBet,
+    #[msg("claim_many expects (betting_pool, match_record, user_bet) triples of valid accounts.")]
+    InvalidClaimAccounts,
+    #[msg("distribute expects (user_bet, owner) pairs for bets on this pool.")]
+    InvalidDistributeAccounts,
+    #[msg("Claim window must be at least one slot.")]
+    InvalidClaimWindow,
+    #[msg("The claim window for this match has closed.")]
+    ClaimWindowClosed,
+    #[msg("The claim window for this match is still open.")]
+    ClaimWindowStillOpen,
+    #[msg("Payouts would exceed the lamports deposited into the pool.")]
+    PayoutExceedsDeposits,
+    #[msg("Account is not a version 0 account of the expected type.")]
+    NotALegacyAccount,
+    #[msg("Game config bounds are inconsistent.")]
+    InvalidGameConfig,
+    #[msg("Betting window is shorter or longer than the configured bounds.")]
+    BettingDurationOutOfBounds,
+    #[msg("Minimum bet threshold is outside the configured bounds.")]
+    MinBetThresholdOutOfBounds,
+    #[msg("No betting deadline given and no default betting duration is configured.")]
+    NoDefaultBettingDuration,
+    #[msg("The first deposit into the house vault is below the minimum.")]
+    FirstDepositTooSmall,
+    #[msg("This bet has no stake to claim; redeem its position tokens instead.")]
+    NothingToClaim,
+    #[msg("migrate_betting_pool expects the pool's claimed bets in ascending address order.")]
+    InvalidMigrationAccounts,
+    #[msg("Some bets already claimed from this pool were not passed to the migration.")]
+    ClaimedBetsMissing,
 }
 
-#[derive(Accounts)]
-pub struct ClaimWinnings<'info> {
-    #[account(
-        mut, // betting_pool is mutable for CPI (SOL transfer from it)
-        seeds = [b"betting_pool".as_ref(), &user_bet.match_id.to_le_bytes()],
-        bump = betting_pool.bump
-    )]
-    pub betting_pool: Account<'info, BettingPool>,
-    #[account(
-        seeds = [b"betting_pool_authority".as_ref(), &user_bet.match_id.to_le_bytes()],
-        bump = betting_pool.pool_authority_bump
-    )]
-    /// CHECK: PDA authority, no data stored.
-    pub betting_pool_authority: AccountInfo<'info>,
-    #[account(
-        seeds = [b"match_record".as_ref(), &user_bet.match_id.to_le_bytes()],
-        bump = match_record.bump
-    )]
-    pub match_record: Account<'info, MatchRecord>,
-    #[account(
-        mut, // user_bet is mutable for `claimed` field update
-        seeds = [
-            b"user_bet".as_ref(),
-            better.key().as_ref(),
-            &user_bet.match_id.to_le_bytes()
-        ],
-        bump = user_bet.bump,
-        constraint = user_bet.better == better.key() @ RpsError::UserBetOwnerMismatch
-    )]
-    pub user_bet: Account<'info, UserBet>,
-    #[account(mut)]
-    pub better: Signer<'info>,
-    pub system_program: Program<'info, System>,
+#[cfg(test)]
+mod user_bet_tests {
+    use super::*;
+
+    #[test]
+    fn only_unclaimed_stakes_are_claimable() {
+        let bet = UserBet {
+            amount: 1_000,
+            ..Default::default()
+        };
+        assert!(bet.require_claimable().is_ok());
+
+        let claimed = UserBet {
+            claimed: true,
+            ..bet.clone()
+        };
+        assert_eq!(
+            claimed.require_claimable().unwrap_err(),
+            RpsError::AlreadyClaimed.into()
+        );
+
+        // Tokenized bets leave the UserBet with only a tokenized amount.
+        let tokenized = UserBet {
+            amount: 0,
+            tokenized_amount: 1_000,
+            ..Default::default()
+        };
+        assert_eq!(
+            tokenized.require_claimable().unwrap_err(),
+            RpsError::NothingToClaim.into()
+        );
+    }
 }
 
-// --- DATA STRUCTURES (ACCOUNTS & ENUMS) ---
-// ... (These should be correct from the previous full version) ...
-#[account]
-#[derive(Default)]
-pub struct GameState {
-    pub authority: Pubkey,
-    pub next_match_id: u64,
-    pub total_matches: u64,
-    pub bump: u8,
+#[cfg(test)]
+mod bet_market_tests {
+    use super::*;
+
+    fn bet(seller: Pubkey) -> UserBet {
+        UserBet {
+            better: seller,
+            origin: seller,
+            amount: 1_000,
+            ..Default::default()
+        }
+    }
+
+    fn listing(seller: Pubkey) -> BetListing {
+        BetListing {
+            seller,
+            price_lamports: 1_200,
+            listed_amount: 1_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn only_open_unclaimed_stakes_can_be_listed() {
+        let bet = bet(Pubkey::new_unique());
+        assert!(bet.require_tradable(MatchStatus::OpenForBetting).is_ok());
+        assert!(bet
+            .require_tradable(MatchStatus::AwaitingResolution)
+            .is_ok());
+        assert_eq!(
+            bet.require_tradable(MatchStatus::Settled).unwrap_err(),
+            RpsError::BetNotTradable.into()
+        );
+
+        let claimed = UserBet {
+            claimed: true,
+            ..bet.clone()
+        };
+        assert_eq!(
+            claimed
+                .require_tradable(MatchStatus::OpenForBetting)
+                .unwrap_err(),
+            RpsError::AlreadyClaimed.into()
+        );
+
+        // Position tokens are traded as tokens, not through listings.
+        let tokenized = UserBet {
+            amount: 0,
+            tokenized_amount: 1_000,
+            ..bet
+        };
+        assert_eq!(
+            tokenized
+                .require_tradable(MatchStatus::OpenForBetting)
+                .unwrap_err(),
+            RpsError::BetAmountZero.into()
+        );
+    }
+
+    #[test]
+    fn buyers_take_the_listed_position() {
+        let seller = Pubkey::new_unique();
+        let buyer = Pubkey::new_unique();
+        let mut bet = bet(seller);
+        assert!(listing(seller).require_buyable(&bet, &buyer).is_ok());
+
+        // A sold bet keeps its origin and can be listed again by the buyer.
+        bet.better = buyer;
+        assert_eq!(bet.origin, seller);
+        assert!(bet.require_tradable(MatchStatus::OpenForBetting).is_ok());
+        let next_buyer = Pubkey::new_unique();
+        assert!(listing(buyer).require_buyable(&bet, &next_buyer).is_ok());
+    }
+
+    #[test]
+    fn top_ups_after_listing_block_the_sale() {
+        let seller = Pubkey::new_unique();
+        let topped_up = UserBet {
+            amount: 1_500,
+            ..bet(seller)
+        };
+        assert_eq!(
+            listing(seller)
+                .require_buyable(&topped_up, &Pubkey::new_unique())
+                .unwrap_err(),
+            RpsError::BetChangedSinceListing.into()
+        );
+    }
+
+    #[test]
+    fn sellers_cannot_buy_their_own_listing() {
+        let seller = Pubkey::new_unique();
+        assert_eq!(
+            listing(seller)
+                .require_buyable(&bet(seller), &seller)
+                .unwrap_err(),
+            RpsError::CannotBuyOwnBet.into()
+        );
+    }
 }
-impl GameState {
-    const LEN: usize = 32 + 8 + 8 + 1;
+
+#[cfg(test)]
+mod claim_many_tests {
+    use super::*;
+    use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+    use std::sync::Once;
+
+    /// Serves the Clock sysvar that debit_claim reads.
+    struct ClockAt1000;
+    impl SyscallStubs for ClockAt1000 {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            let clock = Clock {
+                slot: 1_000,
+                ..Default::default()
+            };
+            unsafe { std::ptr::write(var_addr as *mut Clock, clock) };
+            0
+        }
+    }
+
+    fn stub_clock() {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            set_syscall_stubs(Box::new(ClockAt1000));
+        });
+    }
+
+    /// A program-owned account at `key` holding `account`. Leaked so it lives as long as
+    /// claim_triple needs.
+    fn info<T: AccountSerialize>(
+        key: Pubkey,
+        lamports: u64,
+        account: &T,
+        len: usize,
+    ) -> AccountInfo<'static> {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data.resize(len, 0);
+        AccountInfo::new(
+            Box::leak(Box::new(key)),
+            false,
+            true,
+            Box::leak(Box::new(lamports)),
+            Box::leak(data.into_boxed_slice()),
+            &crate::ID,
+            false,
+            0,
+        )
+    }
+
+    /// A cancelled pool for `match_id` holding 2_000 lamports of refundable bets.
+    fn pool(match_id: u64) -> AccountInfo<'static> {
+        let (key, bump) =
+            Pubkey::find_program_address(&[b"betting_pool", &match_id.to_le_bytes()], &crate::ID);
+        let pool = BettingPool {
+            match_id,
+            bump,
+            status: MatchStatus::CancelledDueToLowBets,
+            total_ai1_bets: 1_000,
+            total_ai2_bets: 1_000,
+            claim_deadline_slot: 2_000,
+            ..Default::default()
+        };
+        info(key, 2_000, &pool, 8 + BettingPool::INIT_SPACE)
+    }
+
+    fn user_bet(owner: Pubkey, match_id: u64, prediction: Prediction) -> AccountInfo<'static> {
+        let (key, bump) = Pubkey::find_program_address(
+            &[b"user_bet", owner.as_ref(), &match_id.to_le_bytes()],
+            &crate::ID,
+        );
+        let bet = UserBet {
+            better: owner,
+            origin: owner,
+            match_id,
+            prediction,
+            amount: 1_000,
+            bump,
+            ..Default::default()
+        };
+        info(key, 0, &bet, 8 + UserBet::INIT_SPACE)
+    }
+
+    fn no_record() -> AccountInfo<'static> {
+        AccountInfo::new(
+            &crate::ID,
+            false,
+            false,
+            Box::leak(Box::new(0)),
+            &mut [],
+            &crate::ID,
+            true,
+            0,
+        )
+    }
+
+    fn claim(triple: Vec<AccountInfo<'static>>, owner: Pubkey) -> Result<u64> {
+        stub_clock();
+        claim_triple(Box::leak(triple.into_boxed_slice()), owner, None)
+    }
+
+    fn load<T: AccountDeserialize>(info: &AccountInfo) -> T {
+        T::try_deserialize(&mut &info.try_borrow_data().unwrap()[..]).unwrap()
+    }
+
+    #[test]
+    fn triples_may_come_in_any_match_order() {
+        let owner = Pubkey::new_unique();
+        let (pool_2, pool_1) = (pool(2), pool(1));
+        let bets = [
+            user_bet(owner, 2, Prediction::Ai1),
+            user_bet(owner, 1, Prediction::Ai2),
+        ];
+        let paid = claim(vec![pool_2.clone(), no_record(), bets[0].clone()], owner).unwrap()
+            + claim(vec![pool_1.clone(), no_record(), bets[1].clone()], owner).unwrap();
+        assert_eq!(paid, 2_000);
+        assert!(bets.iter().all(|bet| load::<UserBet>(bet).claimed));
+        assert_eq!(pool_2.lamports() + pool_1.lamports(), 2_000);
+    }
+
+    #[test]
+    fn bets_on_one_pool_see_each_others_claims() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = pool(3);
+        claim(
+            vec![
+                pool.clone(),
+                no_record(),
+                user_bet(first, 3, Prediction::Ai1),
+            ],
+            first,
+        )
+        .unwrap();
+        claim(
+            vec![
+                pool.clone(),
+                no_record(),
+                user_bet(second, 3, Prediction::Ai2),
+            ],
+            second,
+        )
+        .unwrap();
+        let pool = load::<BettingPool>(&pool);
+        assert_eq!(pool.claimed_count, 2);
+        assert_eq!(pool.total_paid_out, 2_000);
+    }
+
+    #[test]
+    fn a_repeated_bet_is_only_paid_once() {
+        let owner = Pubkey::new_unique();
+        let triple = vec![pool(4), no_record(), user_bet(owner, 4, Prediction::Ai1)];
+        assert_eq!(claim(triple.clone(), owner).unwrap(), 1_000);
+        assert_eq!(
+            claim(triple, owner).unwrap_err(),
+            RpsError::AlreadyClaimed.into()
+        );
+    }
+
+    #[test]
+    fn rejects_a_bet_of_another_owner() {
+        let owner = Pubkey::new_unique();
+        let triple = vec![pool(5), no_record(), user_bet(owner, 5, Prediction::Ai1)];
+        assert_eq!(
+            claim(triple, Pubkey::new_unique()).unwrap_err(),
+            RpsError::UserBetOwnerMismatch.into()
+        );
+    }
+
+    #[test]
+    fn rejects_a_bet_paired_with_another_match() {
+        let owner = Pubkey::new_unique();
+        let triple = vec![pool(6), no_record(), user_bet(owner, 7, Prediction::Ai1)];
+        assert_eq!(
+            claim(triple, owner).unwrap_err(),
+            RpsError::InvalidClaimAccounts.into()
+        );
+    }
 }
 
-#[account]
-#[derive(Default)]
-pub struct BettingPool {
-    pub authority: Pubkey,
-    pub match_id: u64,
-    pub total_ai1_bets: u64,
-    pub total_ai2_bets: u64,
-    pub status: MatchStatus,
-    pub bump: u8,
-    pub pool_authority_bump: u8,
-    pub betting_deadline_slot: u64,
-    pub min_bet_threshold: u64,
+#[cfg(test)]
+mod claim_conservation_tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Up to 10,000 SOL per bet.
+    const MAX_BET: u64 = 10_000 * 1_000_000_000;
+
+    fn prediction() -> impl Strategy<Value = Prediction> {
+        prop_oneof![Just(Prediction::Ai1), Just(Prediction::Ai2)]
+    }
+
+    /// The pool's status and, once settled, the winner.
+    fn ending() -> impl Strategy<Value = (MatchStatus, Winner)> {
+        prop_oneof![
+            Just((MatchStatus::Settled, Winner::Ai1)),
+            Just((MatchStatus::Settled, Winner::Ai2)),
+            Just((MatchStatus::Settled, Winner::Draw)),
+            Just((MatchStatus::CancelledDueToLowBets, Winner::Draw)),
+            Just((MatchStatus::CancelledResolverNoShow, Winner::Draw)),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn sequential_claims_pay_out_exactly_the_pool(
+            bets in prop::collection::vec((prediction(), 1..=MAX_BET), 1..40),
+            (status, winner) in ending(),
+            fee_bps in 0..=MAX_PLATFORM_FEE_BPS,
+            bond in 0..=MAX_BET,
+            order in any::<prop::sample::Index>(),
+        ) {
+            let mut pool = BettingPool {
+                match_id: 7,
+                status,
+                fee_bps,
+                ..Default::default()
+            };
+            for (prediction, amount) in &bets {
+                match prediction {
+                    Prediction::Ai1 => pool.total_ai1_bets += amount,
+                    Prediction::Ai2 => pool.total_ai2_bets += amount,
+                }
+            }
+            if status == MatchStatus::CancelledResolverNoShow {
+                pool.slashed_bond = bond;
+            }
+            let record = MatchRecord {
+                match_id: 7,
+                winner,
+                ..Default::default()
+            };
+            let deposits = pool.total_bets().unwrap() + pool.slashed_bond;
+
+            // Claim in an arbitrary rotation of placement order.
+            let start = order.index(bets.len());
+            let mut balance = deposits;
+            for (prediction, amount) in bets[start..].iter().chain(&bets[..start]) {
+                let claim = compute_claim(&pool, Some(&record), *prediction, *amount, 0).unwrap();
+                pool.record_claim(&claim).unwrap();
+                balance = balance
+                    .checked_sub(claim.payout + claim.fee)
+                    .expect("claims took more than the pool holds");
+            }
+
+            let nobody_won = match status {
+                MatchStatus::Settled => PoolTotals {
+                    ai1: pool.total_ai1_bets,
+                    ai2: pool.total_ai2_bets,
+                }
+                .split(winner)
+                .is_some_and(|(on_winner, _)| on_winner == 0),
+                _ => false,
+            };
+            if nobody_won {
+                prop_assert_eq!(pool.total_paid_out, 0);
+            } else {
+                prop_assert_eq!(pool.total_paid_out + pool.fees_accrued, deposits);
+                prop_assert_eq!(balance, 0);
+            }
+        }
+    }
 }
-impl BettingPool {
-    const LEN: usize = 32 + 8 + 8 + 8 + 1 + 1 + 1 + 8 + 8;
+
+#[cfg(test)]
+mod resolver_bond_tests {
+    use super::*;
+
+    fn take(lamports: u64, data: &mut [u8]) -> (Result<u64>, u64) {
+        let key = Pubkey::new_unique();
+        let mut lamports = lamports;
+        let info = AccountInfo::new(&key, false, true, &mut lamports, data, &crate::ID, false, 0);
+        let taken = take_resolver_bond(&info);
+        let left = info.lamports();
+        (taken, left)
+    }
+
+    #[test]
+    fn slashes_an_existing_bond() {
+        let bond = ResolverBond {
+            match_id: 3,
+            amount: 50_000,
+            ..Default::default()
+        };
+        let mut data = Vec::new();
+        bond.try_serialize(&mut data).unwrap();
+        data.resize(8 + ResolverBond::INIT_SPACE, 0);
+
+        let (taken, left) = take(1_000 + 50_000, &mut data);
+        assert_eq!(taken.unwrap(), 50_000);
+        assert_eq!(left, 1_000);
+        let bond = ResolverBond::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(bond.amount, 0);
+    }
+
+    #[test]
+    fn a_pool_migrated_without_a_bond_can_still_be_cancelled() {
+        let (taken, left) = take(0, &mut []);
+        assert_eq!(taken.unwrap(), 0);
+        assert_eq!(left, 0);
+    }
 }
 
-#[account]
-#[derive(Default)]
-pub struct MatchRecord {
-    pub match_id: u64,
-    pub timestamp: i64,
-    pub ai1_move: Move,
-    pub ai2_move: Move,
-    pub winner: Winner,
-    pub total_bet_amount: u64,
-    pub bump: u8,
+#[cfg(test)]
+mod legacy_bet_tests {
+    use super::*;
+
+    fn v0(match_id: u64, amount: u64, claimed: bool) -> Vec<u8> {
+        let mut data = UserBet::DISCRIMINATOR.to_vec();
+        UserBetV0 {
+            better: Pubkey::new_unique(),
+            match_id,
+            prediction: Prediction::Ai1,
+            amount,
+            claimed,
+            bump: 255,
+        }
+        .serialize(&mut data)
+        .unwrap();
+        data
+    }
+
+    fn v1(match_id: u64, amount: u64, claimed: bool) -> Vec<u8> {
+        let mut data = Vec::new();
+        UserBet {
+            match_id,
+            prediction: Prediction::Ai2,
+            amount,
+            claimed,
+            ..Default::default()
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+        data.resize(8 + UserBet::INIT_SPACE, 0);
+        data
+    }
+
+    /// Loads `bets` from accounts at `keys`, paired up in order.
+    fn load(keys: &[Pubkey], bets: &mut [Vec<u8>]) -> Result<Vec<Bet>> {
+        let mut lamports = vec![0u64; bets.len()];
+        let infos: Vec<AccountInfo> = keys
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(bets.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(key, false, true, lamports, data, &crate::ID, false, 0)
+            })
+            .collect();
+        load_claimed_legacy_bets(&infos, 7)
+    }
+
+    fn sorted_keys(count: usize) -> Vec<Pubkey> {
+        let mut keys: Vec<Pubkey> = (0..count).map(|_| Pubkey::new_unique()).collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn reads_claimed_bets_in_either_layout() {
+        let mut bets = [v0(7, 100, true), v1(7, 200, true), v0(7, 300, false)];
+        let claimed = load(&sorted_keys(3), &mut bets).unwrap();
+        assert_eq!(
+            claimed,
+            vec![
+                Bet {
+                    prediction: Prediction::Ai1,
+                    amount: 100
+                },
+                Bet {
+                    prediction: Prediction::Ai2,
+                    amount: 200
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_repeated_or_unsorted_bets() {
+        let keys = sorted_keys(2);
+        let mut bets = [v0(7, 100, true), v0(7, 100, true)];
+        assert_eq!(
+            load(&[keys[0], keys[0]], &mut bets).unwrap_err(),
+            RpsError::InvalidMigrationAccounts.into()
+        );
+        assert_eq!(
+            load(&[keys[1], keys[0]], &mut bets).unwrap_err(),
+            RpsError::InvalidMigrationAccounts.into()
+        );
+    }
+
+    #[test]
+    fn rejects_bets_on_another_match() {
+        let mut bets = [v0(7, 100, true), v1(8, 100, true)];
+        assert_eq!(
+            load(&sorted_keys(2), &mut bets).unwrap_err(),
+            RpsError::MatchIdMismatch.into()
+        );
+    }
 }
-impl MatchRecord {
-    const LEN: usize = 8 + 8 + 1 + 1 + 1 + 8 + 1;
+
+#[cfg(test)]
+mod keeper_reward_tests {
+    use super::*;
+
+    #[test]
+    fn rewards_come_out_of_uncollected_fees() {
+        let mut pool = BettingPool {
+            fees_accrued: KEEPER_REWARD_LAMPORTS + 1_000,
+            ..Default::default()
+        };
+        assert_eq!(
+            take_keeper_reward(&mut pool).unwrap(),
+            KEEPER_REWARD_LAMPORTS
+        );
+        assert_eq!(take_keeper_reward(&mut pool).unwrap(), 1_000);
+        assert_eq!(take_keeper_reward(&mut pool).unwrap(), 0);
+        assert_eq!(pool.fees_collected, pool.fees_accrued);
+    }
+
+    #[test]
+    fn small_refunds_are_paid_in_full() {
+        let pool = BettingPool {
+            status: MatchStatus::CancelledDueToLowBets,
+            total_ai1_bets: 1_000,
+            total_ai2_bets: 1_000,
+            ..Default::default()
+        };
+        let claim = compute_claim(&pool, None, Prediction::Ai1, 1_000, 0).unwrap();
+        assert_eq!(claim.payout, 1_000);
+
+        // Refunds carry no fee, so the crank takes nothing from them.
+        let mut pool = pool;
+        pool.fees_accrued += claim.fee;
+        assert_eq!(take_keeper_reward(&mut pool).unwrap(), 0);
+    }
 }
 
-#[account]
-#[derive(Default)]
-pub struct UserBet {
-    pub better: Pubkey,
-    pub match_id: u64,
-    pub prediction: Prediction,
-    pub amount: u64,
-    pub claimed: bool,
-    pub bump: u8,
+#[cfg(test)]
+mod fixed_odds_tests {
+    use super::*;
+
+    const MARKET: MarketKind = MarketKind::FixedOdds {
+        ai1_odds_bps: 20_000,
+        ai2_odds_bps: 15_000,
+    };
+
+    /// A vault already reserving 1_000 lamports for another match.
+    fn house_vault() -> HouseVault {
+        HouseVault {
+            total_liability: 1_000,
+            ..Default::default()
+        }
+    }
+
+    /// What place_bet does to the pool and vault for a fixed-odds bet.
+    fn place(
+        pool: &mut BettingPool,
+        vault: &mut HouseVault,
+        prediction: Prediction,
+        previous_amount: u64,
+        amount: u64,
+    ) {
+        match prediction {
+            Prediction::Ai1 => pool.total_ai1_bets += amount,
+            Prediction::Ai2 => pool.total_ai2_bets += amount,
+        }
+        let odds_bps = pool.market.odds_for(prediction).unwrap();
+        pool.add_house_liability(prediction, previous_amount, amount, odds_bps)
+            .unwrap();
+        let worst_case = pool.worst_case_house_liability().unwrap();
+        set_house_reservation(pool, vault, worst_case).unwrap();
+    }
+
+    fn open_pool() -> (BettingPool, HouseVault) {
+        let mut pool = BettingPool {
+            match_id: 4,
+            market: MARKET,
+            ..Default::default()
+        };
+        let mut vault = house_vault();
+        place(&mut pool, &mut vault, Prediction::Ai1, 0, 100);
+        place(&mut pool, &mut vault, Prediction::Ai2, 0, 300);
+        (pool, vault)
+    }
+
+    #[test]
+    fn bets_reserve_the_worst_case_in_the_vault() {
+        let mut pool = BettingPool {
+            market: MARKET,
+            ..Default::default()
+        };
+        let mut vault = house_vault();
+
+        place(&mut pool, &mut vault, Prediction::Ai1, 0, 100);
+        assert_eq!(pool.ai1_house_liability, 200);
+        assert_eq!(pool.house_reserved, 200);
+        assert_eq!(vault.total_liability, 1_200);
+
+        // Ai2 winning would now cost more, so the reservation grows to that.
+        place(&mut pool, &mut vault, Prediction::Ai2, 0, 300);
+        assert_eq!(pool.ai2_house_liability, 450);
+        assert_eq!(pool.house_reserved, 450);
+        assert_eq!(vault.total_liability, 1_450);
+    }
+
+    #[test]
+    fn top_ups_are_reserved_on_the_running_total() {
+        let mut pool = BettingPool {
+            market: MARKET,
+            ..Default::default()
+        };
+        let mut vault = house_vault();
+        // 1 lamport at 1.5x pays 1, but 2 lamports pay 3.
+        place(&mut pool, &mut vault, Prediction::Ai2, 0, 1);
+        place(&mut pool, &mut vault, Prediction::Ai2, 1, 1);
+        assert_eq!(
+            pool.ai2_house_liability,
+            fixed_odds_payout(2, 15_000).unwrap()
+        );
+        assert_eq!(pool.ai2_house_liability, 3);
+    }
+
+    #[test]
+    fn reservation_is_released_as_winners_are_paid() {
+        let (mut pool, mut vault) = open_pool();
+        pool.status = MatchStatus::Settled;
+        let match_record = MatchRecord {
+            match_id: 4,
+            winner: Winner::Ai1,
+            ..Default::default()
+        };
+
+        // Settling trues the reservation up to what Ai1's backers are owed.
+        let owed = pool.final_house_liability(Some(Winner::Ai1)).unwrap();
+        set_house_reservation(&mut pool, &mut vault, owed).unwrap();
+        assert_eq!(vault.total_liability, 1_200);
+
+        let claim =
+            compute_claim(&pool, Some(&match_record), Prediction::Ai1, 100, 20_000).unwrap();
+        assert_eq!(claim.payout, 200);
+        let still_reserved = pool.house_reserved - claim.payout;
+        set_house_reservation(&mut pool, &mut vault, still_reserved).unwrap();
+        assert_eq!(pool.house_reserved, 0);
+        assert_eq!(vault.total_liability, 1_000);
+    }
+
+    #[test]
+    fn cancelled_pools_only_reserve_the_refunds() {
+        let (mut pool, mut vault) = open_pool();
+        pool.status = MatchStatus::CancelledDueToLowBets;
+        let owed = pool.final_house_liability(None).unwrap();
+        set_house_reservation(&mut pool, &mut vault, owed).unwrap();
+        assert_eq!(pool.house_reserved, 400);
+        assert_eq!(vault.total_liability, 1_400);
+
+        let claim = compute_claim(&pool, None, Prediction::Ai2, 300, 15_000).unwrap();
+        assert_eq!(claim.payout, 300);
+
+        // Sweeping an expired pool stops reserving anything for it.
+        set_house_reservation(&mut pool, &mut vault, 0).unwrap();
+        assert_eq!(vault.total_liability, 1_000);
+    }
+
+    #[test]
+    fn claims_pay_the_locked_odds() {
+        let (mut pool, _) = open_pool();
+        pool.status = MatchStatus::Settled;
+        let record = |winner| MatchRecord {
+            match_id: 4,
+            winner,
+            ..Default::default()
+        };
+
+        // The odds stored on the bet are paid, whatever the market quotes.
+        let claim = compute_claim(
+            &pool,
+            Some(&record(Winner::Ai2)),
+            Prediction::Ai2,
+            300,
+            12_500,
+        )
+        .unwrap();
+        assert_eq!(claim.payout, fixed_odds_payout(300, 12_500).unwrap());
+        assert_eq!(claim.payout, 375);
+        assert_eq!((claim.fee, claim.stake), (0, 300));
+
+        let lost = compute_claim(
+            &pool,
+            Some(&record(Winner::Ai2)),
+            Prediction::Ai1,
+            100,
+            20_000,
+        )
+        .unwrap();
+        assert_eq!(lost.payout, 0);
+        let drawn = compute_claim(
+            &pool,
+            Some(&record(Winner::Draw)),
+            Prediction::Ai1,
+            100,
+            20_000,
+        )
+        .unwrap();
+        assert_eq!(drawn.payout, 100);
+    }
 }
-impl UserBet {
-    const LEN: usize = 32 + 8 + 1 + 8 + 1 + 1;
+
+#[cfg(test)]
+mod claim_window_tests {
+    use super::*;
+
+    fn pool() -> BettingPool {
+        let mut pool = BettingPool {
+            status: MatchStatus::Settled,
+            claim_window_slots: 100,
+            ..Default::default()
+        };
+        pool.open_claim_window(1_000).unwrap();
+        pool
+    }
+
+    #[test]
+    fn open_window_pays_claims_and_cannot_be_swept() {
+        let pool = pool();
+        assert_eq!(pool.claim_deadline_slot, 1_100);
+        assert!(pool.require_claim_window_open(1_099).is_ok());
+        assert_eq!(
+            pool.require_sweepable(1_099).unwrap_err(),
+            RpsError::ClaimWindowStillOpen.into()
+        );
+    }
+
+    #[test]
+    fn expired_window_rejects_claims_and_can_be_swept() {
+        let pool = pool();
+        assert_eq!(
+            pool.require_claim_window_open(1_100).unwrap_err(),
+            RpsError::ClaimWindowClosed.into()
+        );
+        assert!(pool.require_sweepable(1_100).is_ok());
+    }
+
+    #[test]
+    fn unfinished_pools_cannot_be_swept() {
+        let pool = BettingPool {
+            status: MatchStatus::AwaitingResolution,
+            ..Default::default()
+        };
+        assert_eq!(
+            pool.require_sweepable(u64::MAX).unwrap_err(),
+            RpsError::MatchNotReadyForClaimOrRefund.into()
+        );
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
-pub enum MatchStatus {
-    #[default]
-    OpenForBetting,
-    AwaitingResolution,
-    Settled,
-    CancelledDueToLowBets,
+#[cfg(test)]
+mod lp_share_tests {
+    use super::*;
+
+    #[test]
+    fn first_deposit_locks_shares() {
+        assert_eq!(
+            lp_shares_for_deposit(MIN_FIRST_DEPOSIT_LAMPORTS, 0, 0).unwrap(),
+            MIN_FIRST_DEPOSIT_LAMPORTS - LOCKED_LP_SHARES
+        );
+        assert_eq!(
+            lp_shares_for_deposit(MIN_FIRST_DEPOSIT_LAMPORTS - 1, 0, 0).unwrap_err(),
+            RpsError::FirstDepositTooSmall.into()
+        );
+    }
+
+    #[test]
+    fn first_deposit_does_not_take_an_earlier_balance() {
+        // Fees reached the vault before anyone provided liquidity.
+        let earlier = 5 * 1_000_000_000;
+        let deposit = 1_000_000_000;
+        let shares = lp_shares_for_deposit(deposit, earlier, 0).unwrap();
+        let supply = shares + LOCKED_LP_SHARES;
+        let redeemable = lp_shares_value(shares, earlier + deposit, supply).unwrap();
+        assert!(redeemable <= deposit - LOCKED_LP_SHARES);
+        // Within what one share is worth of the 1:1 credit on an empty vault.
+        assert!(deposit - LOCKED_LP_SHARES - redeemable <= (earlier + deposit) / supply + 1);
+        let locked = lp_shares_value(LOCKED_LP_SHARES, earlier + deposit, supply).unwrap();
+        assert!(locked >= earlier + LOCKED_LP_SHARES);
+    }
+
+    #[test]
+    fn later_deposits_are_priced_against_net_assets() {
+        // The vault doubled in value, so each lamport buys half a share.
+        assert_eq!(lp_shares_for_deposit(1_000, 2_000, 1_000).unwrap(), 500);
+        // Shares round down, in the vault's favour.
+        assert_eq!(lp_shares_for_deposit(3, 2_000, 1_000).unwrap(), 1);
+        assert_eq!(
+            lp_shares_for_deposit(1_000, 0, 1_000).unwrap_err(),
+            RpsError::HouseVaultInsolvent.into()
+        );
+    }
+
+    #[test]
+    fn donations_cannot_round_deposits_away() {
+        // The attacker keeps a single share next to the locked ones and donates 1,000 SOL
+        // straight into the vault before a victim deposits.
+        let supply = LOCKED_LP_SHARES + 1;
+        let donation = 1_000 * 1_000_000_000;
+        let net_assets = supply + donation;
+        let deposit = 1_000_000_000;
+        let shares = lp_shares_for_deposit(deposit, net_assets, supply).unwrap();
+        assert!(shares > 0);
+        let redeemable = lp_shares_value(shares, net_assets + deposit, supply + shares).unwrap();
+        // The victim loses at most what one share is worth to rounding.
+        assert!(deposit - redeemable <= (net_assets + deposit) / (supply + shares) + 1);
+    }
+
+    #[test]
+    fn shares_redeem_pro_rata_rounding_down() {
+        assert_eq!(lp_shares_value(500, 2_000, 1_000).unwrap(), 1_000);
+        assert_eq!(lp_shares_value(1, 2_000, 3).unwrap(), 666);
+        assert_eq!(
+            lp_shares_value(1, 2_000, 0).unwrap_err(),
+            RpsError::DivisionByZero.into()
+        );
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
-pub enum Move {
-    #[default]
-    Rock,
-    Paper,
-    Scissors,
+#[cfg(test)]
+mod betting_time_tests {
+    use super::*;
+
+    fn clock() -> Clock {
+        Clock {
+            slot: 1_000,
+            unix_timestamp: 1_700_000_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn deadlines_pass_at_their_slot_or_second() {
+        let clock = clock();
+        assert!(BettingTime::Slot(1_000).has_passed(&clock));
+        assert!(!BettingTime::Slot(1_001).has_passed(&clock));
+        assert!(BettingTime::UnixTimestamp(clock.unix_timestamp).has_passed(&clock));
+        assert!(!BettingTime::UnixTimestamp(clock.unix_timestamp + 1).has_passed(&clock));
+    }
+
+    #[test]
+    fn orders_times_of_the_same_kind() {
+        let clock = clock();
+        assert!(BettingTime::Slot(5).is_before(&BettingTime::Slot(5), &clock));
+        assert!(!BettingTime::Slot(6).is_before(&BettingTime::Slot(5), &clock));
+        assert!(BettingTime::UnixTimestamp(1).is_before(&BettingTime::UnixTimestamp(2), &clock));
+    }
+
+    #[test]
+    fn orders_mixed_kinds_by_approximate_slot() {
+        let clock = clock();
+        // 40 seconds from now is about 100 slots away.
+        let in_40s = BettingTime::UnixTimestamp(clock.unix_timestamp + 40);
+        assert_eq!(in_40s.approx_slot(&clock), 1_100);
+        assert!(in_40s.is_before(&BettingTime::Slot(1_200), &clock));
+        assert!(!in_40s.is_before(&BettingTime::Slot(1_050), &clock));
+        assert!(BettingTime::Slot(1_050).is_before(&in_40s, &clock));
+        assert!(!BettingTime::Slot(1_200).is_before(&in_40s, &clock));
+    }
 }
-impl Move {
-    fn from_u8(value: u8) -> Result<Self> {
-        match value {
-            0 => Ok(Move::Rock),
-            1 => Ok(Move::Paper),
-            2 => Ok(Move::Scissors),
-            _ => Err(RpsError::InvalidMoveValue.into()),
+
+#[cfg(test)]
+mod bet_limits_tests {
+    use super::*;
+
+    fn limits() -> BetLimits {
+        BetLimits {
+            min_bet: Some(100),
+            max_bet: Some(1_000),
+            max_per_user: Some(1_500),
+            max_pool_size: Some(5_000),
+        }
+    }
+
+    #[test]
+    fn accepts_limits_above_the_minimum_bet() {
+        assert!(limits().validate().is_ok());
+        assert!(BetLimits::default().validate().is_ok());
+        let equal = BetLimits {
+            min_bet: Some(100),
+            max_bet: Some(100),
+            max_per_user: Some(100),
+            max_pool_size: Some(100),
+        };
+        assert!(equal.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_a_cap_below_the_minimum_bet() {
+        for limits in [
+            BetLimits {
+                max_bet: Some(99),
+                ..limits()
+            },
+            BetLimits {
+                max_per_user: Some(99),
+                ..limits()
+            },
+            BetLimits {
+                max_pool_size: Some(99),
+                ..limits()
+            },
+        ] {
+            assert_eq!(
+                limits.validate().unwrap_err(),
+                RpsError::InvalidBetLimits.into()
+            );
         }
     }
+
+    #[test]
+    fn bounds_each_bet() {
+        assert!(limits().check(100, 0, 0).is_ok());
+        assert!(limits().check(1_000, 0, 0).is_ok());
+        assert_eq!(
+            limits().check(99, 0, 0).unwrap_err(),
+            RpsError::BetBelowMinimum.into()
+        );
+        assert_eq!(
+            limits().check(1_001, 0, 0).unwrap_err(),
+            RpsError::BetAboveMaximum.into()
+        );
+    }
+
+    #[test]
+    fn counts_earlier_bets_towards_the_per_user_cap() {
+        assert!(limits().check(500, 1_000, 1_000).is_ok());
+        assert_eq!(
+            limits().check(501, 1_000, 1_000).unwrap_err(),
+            RpsError::UserBetLimitExceeded.into()
+        );
+    }
+
+    #[test]
+    fn counts_both_sides_towards_the_pool_cap() {
+        assert!(limits().check(1_000, 0, 4_000).is_ok());
+        assert_eq!(
+            limits().check(1_000, 0, 4_001).unwrap_err(),
+            RpsError::PoolSizeLimitExceeded.into()
+        );
+        assert_eq!(
+            limits().check(1_000, 0, u64::MAX).unwrap_err(),
+            RpsError::Overflow.into()
+        );
+    }
+
+    #[test]
+    fn unset_limits_allow_any_bet() {
+        assert!(BetLimits::default().check(u64::MAX, 0, 0).is_ok());
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
-pub enum Winner {
-    #[default]
-    Ai1,
-    Ai2,
-    Draw,
+#[cfg(test)]
+mod imbalance_guard_tests {
+    use super::*;
+
+    fn guard(max_side_ratio_bps: u64) -> ImbalanceGuard {
+        ImbalanceGuard { max_side_ratio_bps }
+    }
+
+    #[test]
+    fn allows_a_pool_at_exactly_the_ratio() {
+        // 3:2 is exactly 15_000 bps, on either side.
+        assert!(!guard(15_000).is_exceeded(3_000, 2_000));
+        assert!(!guard(15_000).is_exceeded(2_000, 3_000));
+        assert!(guard(15_000).is_exceeded(3_001, 2_000));
+        assert!(guard(15_000).is_exceeded(2_000, 3_001));
+    }
+
+    #[test]
+    fn a_one_to_one_guard_only_allows_even_pools() {
+        assert!(!guard(BPS_DENOMINATOR).is_exceeded(1_000, 1_000));
+        assert!(guard(BPS_DENOMINATOR).is_exceeded(1_001, 1_000));
+    }
+
+    #[test]
+    fn a_one_sided_pool_always_exceeds_it() {
+        assert!(guard(u64::MAX).is_exceeded(1_000, 0));
+        assert!(guard(u64::MAX).is_exceeded(0, 1_000));
+        assert!(guard(u64::MAX).is_exceeded(0, 0));
+    }
+
+    #[test]
+    fn does_not_overflow_on_large_pools() {
+        assert!(!guard(20_000).is_exceeded(u64::MAX - 1, u64::MAX / 2));
+        assert!(guard(BPS_DENOMINATOR).is_exceeded(u64::MAX, u64::MAX - 1));
+    }
+
+    #[test]
+    fn rejects_a_ratio_below_one_to_one() {
+        assert!(guard(BPS_DENOMINATOR).validate().is_ok());
+        assert_eq!(
+            guard(BPS_DENOMINATOR - 1).validate().unwrap_err(),
+            RpsError::InvalidImbalanceGuard.into()
+        );
+        assert_eq!(
+            guard(0).validate().unwrap_err(),
+            RpsError::InvalidImbalanceGuard.into()
+        );
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
-pub enum Prediction {
-    #[default]
-    Ai1,
-    Ai2,
+// Every account is created with a single `init`, which cannot allocate more than
+// MAX_PERMITTED_DATA_INCREASE bytes through CPI. Catch an oversized layout at build time.
+const _: () = {
+    use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+    assert!(8 + GameState::INIT_SPACE <= MAX_PERMITTED_DATA_INCREASE);
+    assert!(8 + BettingPool::INIT_SPACE <= MAX_PERMITTED_DATA_INCREASE);
+    assert!(8 + MatchRecord::INIT_SPACE <= MAX_PERMITTED_DATA_INCREASE);
+    assert!(8 + UserBet::INIT_SPACE <= MAX_PERMITTED_DATA_INCREASE);
+    // Migrations only ever grow an account.
+    assert!(legacy::GameStateV0::LEN < GameState::INIT_SPACE);
+    assert!(legacy::BettingPoolV0::LEN < BettingPool::INIT_SPACE);
+    assert!(legacy::MatchRecordV0::LEN < MatchRecord::INIT_SPACE);
+    assert!(legacy::UserBetV0::LEN < UserBet::INIT_SPACE);
+};
+
+#[cfg(test)]
+mod game_config_tests {
+    use super::*;
+
+    fn config() -> GameConfig {
+        GameConfig {
+            default_betting_duration_slots: 1_500,
+            min_betting_duration_slots: 150,
+            max_betting_duration_slots: 216_000,
+            resolution_timeout_slots: 1_500,
+            dispute_window_slots: 150,
+            claim_window_slots: 1_512_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accepts_a_sensible_config() {
+        assert!(config().validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_an_empty_dispute_window() {
+        let config = GameConfig {
+            dispute_window_slots: 0,
+            ..config()
+        };
+        assert_eq!(
+            config.validate().unwrap_err(),
+            RpsError::InvalidGameConfig.into()
+        );
+    }
+
+    #[test]
+    fn rejects_a_zero_resolution_timeout() {
+        let config = GameConfig {
+            resolution_timeout_slots: 0,
+            ..config()
+        };
+        assert_eq!(
+            config.validate().unwrap_err(),
+            RpsError::InvalidGameConfig.into()
+        );
+    }
 }
 
-#[error_code]
-pub enum RpsError {
-    #[msg("A numeric operation caused an overflow.")]
-    Overflow,
-    #[msg("Action unauthorized. Signer does not have required permissions.")]
-    Unauthorized,
-    #[msg("Betting is closed or not yet open for this match.")]
-    BettingClosedOrNotOpen,
-    #[msg("Betting deadline for this match has already passed.")]
-    BettingDeadlinePassed,
-    #[msg("Bet amount must be greater than zero.")]
-    BetAmountZero,
-    #[msg("Invalid prediction value (must be 0 for AI1 or 1 for AI2).")]
-    InvalidPrediction,
-    #[msg("The provided match_id does not match the account's match_id.")]
-    MatchIdMismatch,
-    #[msg("The winner for this match has not been set yet (internal error).")]
-    WinnerNotSet,
-    #[msg("This bet has already been claimed.")]
-    AlreadyClaimed,
-    #[msg("Match is not in a state where claims or refunds are allowed.")]
-    MatchNotReadyForClaimOrRefund,
-    #[msg("No bets were placed on the winning outcome, or division by zero would occur.")]
-    NoWinningBets,
-    #[msg("The signer does not own this bet receipt.")]
-    UserBetOwnerMismatch,
-    #[msg("Division by zero occurred during payout calculation.")]
-    DivisionByZero,
-    #[msg("Invalid move value (must be 0 for Rock, 1 for Paper, 2 for Scissors).")]
-    InvalidMoveValue,
-    #[msg("Betting deadline for this match has not been reached yet.")]
-    BettingDeadlineNotReached,
-    #[msg("This match is not open for betting or has already been processed by deadline check.")]
-    MatchNotOpenOrAlreadyProcessed,
-    #[msg("Match is not in AwaitingResolution state, cannot resolve with AI moves.")]
-    MatchNotAwaitingResolution,
-    #[msg("Match record's ID does not match the betting pool's ID during claim.")]
-    MatchIdMismatchInRecord,
+#[cfg(test)]
+mod account_size_tests {
+    use super::*;
+
+    fn serialized_len<T: AnchorSerialize>(value: &T) -> usize {
+        let mut data = Vec::new();
+        value.serialize(&mut data).unwrap();
+        data.len()
+    }
+
+    #[test]
+    fn fixed_size_accounts_match_init_space() {
+        assert_eq!(serialized_len(&GameState::default()), GameState::INIT_SPACE);
+        assert_eq!(serialized_len(&Treasury::default()), Treasury::INIT_SPACE);
+        assert_eq!(
+            serialized_len(&MatchRecord::default()),
+            MatchRecord::INIT_SPACE
+        );
+        assert_eq!(serialized_len(&Dispute::default()), Dispute::INIT_SPACE);
+        assert_eq!(
+            serialized_len(&ResolverBond::default()),
+            ResolverBond::INIT_SPACE
+        );
+        assert_eq!(serialized_len(&UserBet::default()), UserBet::INIT_SPACE);
+        assert_eq!(
+            serialized_len(&BetListing::default()),
+            BetListing::INIT_SPACE
+        );
+        assert_eq!(
+            serialized_len(&HouseVault::default()),
+            HouseVault::INIT_SPACE
+        );
+        assert_eq!(
+            serialized_len(&LiquidityWithdrawal::default()),
+            LiquidityWithdrawal::INIT_SPACE
+        );
+    }
+
+    #[test]
+    fn betting_pool_init_space_fits_largest_variants() {
+        let largest = BettingPool {
+            betting_deadline: BettingTime::UnixTimestamp(i64::MAX),
+            betting_opens_at: BettingTime::UnixTimestamp(i64::MAX),
+            bet_limits: BetLimits {
+                min_bet: Some(u64::MAX),
+                max_bet: Some(u64::MAX),
+                max_per_user: Some(u64::MAX),
+                max_pool_size: Some(u64::MAX),
+            },
+            imbalance_guard: Some(ImbalanceGuard {
+                max_side_ratio_bps: u64::MAX,
+            }),
+            market: MarketKind::FixedOdds {
+                ai1_odds_bps: u64::MAX,
+                ai2_odds_bps: u64::MAX,
+            },
+            ..Default::default()
+        };
+        assert_eq!(serialized_len(&largest), BettingPool::INIT_SPACE);
+        assert!(serialized_len(&BettingPool::default()) <= BettingPool::INIT_SPACE);
+    }
+
+    #[test]
+    fn legacy_lengths_match_their_layouts() {
+        let game_state = legacy::GameStateV0 {
+            authority: Pubkey::default(),
+            next_match_id: 0,
+            total_matches: 0,
+            bump: 0,
+        };
+        assert_eq!(serialized_len(&game_state), legacy::GameStateV0::LEN);
+
+        let betting_pool = legacy::BettingPoolV0 {
+            authority: Pubkey::default(),
+            match_id: 0,
+            total_ai1_bets: 0,
+            total_ai2_bets: 0,
+            status: MatchStatus::OpenForBetting,
+            bump: 0,
+            pool_authority_bump: 0,
+            betting_deadline_slot: 0,
+            min_bet_threshold: 0,
+        };
+        assert_eq!(serialized_len(&betting_pool), legacy::BettingPoolV0::LEN);
+
+        let match_record = legacy::MatchRecordV0 {
+            match_id: 0,
+            timestamp: 0,
+            ai1_move: Move::Rock,
+            ai2_move: Move::Rock,
+            winner: Winner::Draw,
+            total_bet_amount: 0,
+            bump: 0,
+        };
+        assert_eq!(serialized_len(&match_record), legacy::MatchRecordV0::LEN);
+
+        let user_bet = legacy::UserBetV0 {
+            better: Pubkey::default(),
+            match_id: 0,
+            prediction: Prediction::Ai1,
+            amount: 0,
+            claimed: false,
+            bump: 0,
+        };
+        assert_eq!(serialized_len(&user_bet), legacy::UserBetV0::LEN);
+    }
 }
@@ -0,0 +1,136 @@
+// breakin/programs/breakin/src/payout.rs
+//! Payout math shared by the program and off-chain clients.
+//!
+//! The product of two lamport amounts overflows u64 once pools reach a few thousand SOL,
+//! so every intermediate here is u128 and only the final result is narrowed to u64.
+use anchor_lang::prelude::*;
+
+use crate::{RpsError, BPS_DENOMINATOR};
+
+/// What a winning parimutuel bet is paid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Payout {
+    /// Share of the losing side before fees.
+    pub profit: u64,
+    /// Platform fee withheld from `profit`.
+    pub fee: u64,
+    /// Stake plus profit minus fee.
+    pub total: u64,
+}
+
+/// `total * part / whole`, rounded down.
+pub fn pro_rata(total: u64, part: u64, whole: u64) -> Result<u64> {
+    require!(whole > 0, RpsError::DivisionByZero);
+    let share = (total as u128)
+        .checked_mul(part as u128)
+        .ok_or(RpsError::Overflow)?
+        / whole as u128;
+    Ok(u64::try_from(share).map_err(|_| RpsError::Overflow)?)
+}
+
+/// Payout for a winning parimutuel bet of `amount` when `total_on_winner` was staked on
+/// the winning side and `total_on_loser` on the other, with `fee_bps` taken from profit.
+pub fn compute_payout(
+    amount: u64,
+    total_on_winner: u64,
+    total_on_loser: u64,
+    fee_bps: u64,
+) -> Result<Payout> {
+    require!(total_on_winner > 0, RpsError::NoWinningBets);
+    let profit = pro_rata(total_on_loser, amount, total_on_winner)?;
+    payout_with_profit(amount, profit, fee_bps)
+}
+
+/// Stake plus `profit`, less the platform fee. The fee never touches the stake.
+pub fn payout_with_profit(amount: u64, profit: u64, fee_bps: u64) -> Result<Payout> {
+    let fee = pro_rata(profit, fee_bps, BPS_DENOMINATOR)?;
+    let net = profit.checked_sub(fee).ok_or(RpsError::Overflow)?;
+    let total = amount.checked_add(net).ok_or(RpsError::Overflow)?;
+    Ok(Payout { profit, fee, total })
+}
+
+/// Total returned to a winning fixed-odds bet (stake included) at `odds_bps` decimal odds.
+pub fn fixed_odds_payout(amount: u64, odds_bps: u64) -> Result<u64> {
+    pro_rata(amount, odds_bps, BPS_DENOMINATOR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOL: u64 = 1_000_000_000;
+
+    #[test]
+    fn splits_losing_side_pro_rata() {
+        let payout = compute_payout(2 * SOL, 4 * SOL, 6 * SOL, 0).unwrap();
+        assert_eq!(payout.profit, 3 * SOL);
+        assert_eq!(payout.fee, 0);
+        assert_eq!(payout.total, 5 * SOL);
+    }
+
+    #[test]
+    fn large_pools_do_not_overflow() {
+        // 5,000 SOL on each side: the old u64 product overflowed here.
+        let side = 5_000 * SOL;
+        let payout = compute_payout(side, side, side, 0).unwrap();
+        assert_eq!(payout.total, 2 * side);
+
+        let payout = compute_payout(1, side, side, 0).unwrap();
+        assert_eq!(payout.total, 2);
+    }
+
+    #[test]
+    fn rounds_profit_down() {
+        let payout = compute_payout(1, 3, 2, 0).unwrap();
+        assert_eq!(payout.profit, 0);
+        assert_eq!(payout.total, 1);
+    }
+
+    #[test]
+    fn fee_comes_out_of_profit_only() {
+        let payout = compute_payout(SOL, SOL, SOL, 250).unwrap();
+        assert_eq!(payout.profit, SOL);
+        assert_eq!(payout.fee, SOL / 40);
+        assert_eq!(payout.total, 2 * SOL - SOL / 40);
+
+        // Nothing to win means nothing to charge.
+        let payout = compute_payout(SOL, SOL, 0, 1_000).unwrap();
+        assert_eq!(payout.fee, 0);
+        assert_eq!(payout.total, SOL);
+    }
+
+    #[test]
+    fn fee_above_profit_is_an_error() {
+        // A fee rate over 100% would otherwise underflow and eat into the stake.
+        assert_eq!(
+            payout_with_profit(SOL, SOL, BPS_DENOMINATOR + 1).unwrap_err(),
+            RpsError::Overflow.into()
+        );
+        assert_eq!(
+            payout_with_profit(SOL, SOL, BPS_DENOMINATOR).unwrap().total,
+            SOL
+        );
+    }
+
+    #[test]
+    fn empty_winning_side_is_an_error() {
+        assert_eq!(
+            compute_payout(SOL, 0, SOL, 0).unwrap_err(),
+            RpsError::NoWinningBets.into()
+        );
+    }
+
+    #[test]
+    fn result_too_large_for_u64_is_an_error() {
+        assert_eq!(
+            compute_payout(u64::MAX, 1, 2, 0).unwrap_err(),
+            RpsError::Overflow.into()
+        );
+    }
+
+    #[test]
+    fn fixed_odds_include_stake() {
+        assert_eq!(fixed_odds_payout(SOL, 25_000).unwrap(), 5 * SOL / 2);
+        assert_eq!(fixed_odds_payout(3, 15_000).unwrap(), 4);
+    }
+}
@@ -0,0 +1,204 @@
+// breakin/programs/breakin/src/settlement.rs
+//! Pure settlement rules for a single parimutuel bet.
+//!
+//! Nothing here touches accounts or the clock, so the same function backs the on-chain
+//! claim path and can be checked exhaustively off-chain. Fixed-odds winnings, the last
+//! claimant's dust and slashed bond shares are layered on top by `compute_claim`.
+use anchor_lang::prelude::*;
+
+use crate::payout::compute_payout;
+use crate::{Prediction, Winner};
+
+/// Lamports staked on each side of a pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolTotals {
+    pub ai1: u64,
+    pub ai2: u64,
+}
+
+impl PoolTotals {
+    /// (winning side, losing side) totals for `winner`. Draws have no winning side.
+    pub fn split(&self, winner: Winner) -> Option<(u64, u64)> {
+        match winner {
+            Winner::Ai1 => Some((self.ai1, self.ai2)),
+            Winner::Ai2 => Some((self.ai2, self.ai1)),
+            Winner::Draw => None,
+        }
+    }
+}
+
+/// How the match ended, as far as payouts are concerned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The match was played and `Winner` is final.
+    Settled(Winner),
+    /// The match was cancelled and every stake is returned.
+    Refunded,
+}
+
+/// A single position in the pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bet {
+    pub prediction: Prediction,
+    pub amount: u64,
+}
+
+/// What one bet is owed once the match is over.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Settlement {
+    /// Total lamports paid to the holder, stake included.
+    pub payout: u64,
+    /// Platform fee withheld from the profit.
+    pub fee: u64,
+    /// Part of `payout` that returns the stake; zero for losing bets.
+    pub stake: u64,
+}
+
+/// Settles `bet` against a pool holding `totals` with `fee_bps` taken from profit.
+pub fn settle(totals: PoolTotals, outcome: Outcome, bet: Bet, fee_bps: u64) -> Result<Settlement> {
+    let winner = match outcome {
+        Outcome::Refunded => return Ok(refund(bet.amount)),
+        Outcome::Settled(winner) => winner,
+    };
+    let Some((total_on_winner, total_on_loser)) = totals.split(winner) else {
+        return Ok(refund(bet.amount));
+    };
+    if !predicted(bet.prediction, winner) {
+        return Ok(Settlement::default());
+    }
+
+    let payout = compute_payout(bet.amount, total_on_winner, total_on_loser, fee_bps)?;
+    Ok(Settlement {
+        payout: payout.total,
+        fee: payout.fee,
+        stake: bet.amount,
+    })
+}
+
+/// Whether a bet on `prediction` backed `winner`.
+pub fn predicted(prediction: Prediction, winner: Winner) -> bool {
+    matches!(
+        (prediction, winner),
+        (Prediction::Ai1, Winner::Ai1) | (Prediction::Ai2, Winner::Ai2)
+    )
+}
+
+fn refund(amount: u64) -> Settlement {
+    Settlement {
+        payout: amount,
+        fee: 0,
+        stake: amount,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MAX_PLATFORM_FEE_BPS;
+    use proptest::prelude::*;
+
+    /// Up to 10,000 SOL per bet.
+    const MAX_BET: u64 = 10_000 * 1_000_000_000;
+
+    fn prediction() -> impl Strategy<Value = Prediction> {
+        prop_oneof![Just(Prediction::Ai1), Just(Prediction::Ai2)]
+    }
+
+    fn winner() -> impl Strategy<Value = Winner> {
+        prop_oneof![Just(Winner::Ai1), Just(Winner::Ai2), Just(Winner::Draw)]
+    }
+
+    fn bets() -> impl Strategy<Value = Vec<Bet>> {
+        prop::collection::vec(
+            (prediction(), 1..=MAX_BET).prop_map(|(prediction, amount)| Bet { prediction, amount }),
+            1..50,
+        )
+    }
+
+    fn totals_of(bets: &[Bet]) -> PoolTotals {
+        bets.iter().fold(PoolTotals::default(), |mut totals, bet| {
+            match bet.prediction {
+                Prediction::Ai1 => totals.ai1 += bet.amount,
+                Prediction::Ai2 => totals.ai2 += bet.amount,
+            }
+            totals
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn payouts_and_fees_never_exceed_the_pool(
+            bets in bets(),
+            winner in winner(),
+            fee_bps in 0..=MAX_PLATFORM_FEE_BPS,
+        ) {
+            let totals = totals_of(&bets);
+            let mut paid: u128 = 0;
+            for bet in &bets {
+                match settle(totals, Outcome::Settled(winner), *bet, fee_bps) {
+                    Ok(settlement) => paid += (settlement.payout + settlement.fee) as u128,
+                    // Only possible when nobody backed the winner.
+                    Err(_) => prop_assert_eq!(totals.split(winner).unwrap().0, 0),
+                }
+            }
+            prop_assert!(paid <= totals.ai1 as u128 + totals.ai2 as u128);
+        }
+
+        #[test]
+        fn larger_winning_stakes_never_pay_less(
+            small in 1..=MAX_BET,
+            extra in 0..=MAX_BET,
+            others_on_winner in 0..=MAX_BET,
+            total_on_loser in 0..=MAX_BET,
+            fee_bps in 0..=MAX_PLATFORM_FEE_BPS,
+        ) {
+            let large = small + extra;
+            let totals = PoolTotals { ai1: large + others_on_winner, ai2: total_on_loser };
+            let outcome = Outcome::Settled(Winner::Ai1);
+            let pay = |amount| {
+                settle(totals, outcome, Bet { prediction: Prediction::Ai1, amount }, fee_bps)
+                    .unwrap()
+            };
+            let (small_pay, large_pay) = (pay(small), pay(large));
+            prop_assert!(small_pay.payout <= large_pay.payout);
+            prop_assert!(small_pay.fee <= large_pay.fee);
+        }
+
+        #[test]
+        fn winners_keep_their_stake_and_losers_get_nothing(
+            bet in (prediction(), 1..=MAX_BET),
+            other_on_ai1 in 0..=MAX_BET,
+            other_on_ai2 in 0..=MAX_BET,
+            winner in prop_oneof![Just(Winner::Ai1), Just(Winner::Ai2)],
+            fee_bps in 0..=MAX_PLATFORM_FEE_BPS,
+        ) {
+            let bet = Bet { prediction: bet.0, amount: bet.1 };
+            let mut totals = PoolTotals { ai1: other_on_ai1, ai2: other_on_ai2 };
+            match bet.prediction {
+                Prediction::Ai1 => totals.ai1 += bet.amount,
+                Prediction::Ai2 => totals.ai2 += bet.amount,
+            }
+            let settlement = settle(totals, Outcome::Settled(winner), bet, fee_bps).unwrap();
+            if predicted(bet.prediction, winner) {
+                prop_assert!(settlement.payout >= bet.amount);
+                prop_assert_eq!(settlement.stake, bet.amount);
+            } else {
+                prop_assert_eq!(settlement, Settlement::default());
+            }
+        }
+
+        #[test]
+        fn draws_and_cancellations_refund_the_stake_without_fees(
+            bets in bets(),
+            cancelled in any::<bool>(),
+            fee_bps in 0..=MAX_PLATFORM_FEE_BPS,
+        ) {
+            let totals = totals_of(&bets);
+            let outcome = if cancelled { Outcome::Refunded } else { Outcome::Settled(Winner::Draw) };
+            for bet in &bets {
+                let settlement = settle(totals, outcome, *bet, fee_bps).unwrap();
+                prop_assert_eq!(settlement, refund(bet.amount));
+            }
+        }
+    }
+}